@@ -0,0 +1,88 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use utf8_parser::Utf8Parser;
+
+fn corpus() -> [(&'static str, String); 3] {
+    [
+        (
+            "ascii",
+            "The quick brown fox jumps over the lazy dog. ".repeat(200),
+        ),
+        (
+            "mixed",
+            "Thé quick brown 🦊 jumps over the lazy 🐕. ".repeat(200),
+        ),
+        (
+            "cjk",
+            "素早い茶色の狐がのろまな犬を飛び越える。".repeat(200),
+        ),
+    ]
+}
+
+fn push_slice(c: &mut Criterion) {
+    let mut group = c.benchmark_group("push_slice");
+    for (name, text) in corpus() {
+        let bytes = text.as_bytes();
+        let mut out = vec!['\0'; bytes.len()];
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let mut parser = Utf8Parser::new();
+                parser.push_slice(black_box(bytes), &mut out).unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn push(c: &mut Criterion) {
+    let mut group = c.benchmark_group("push");
+    for (name, text) in corpus() {
+        let bytes = text.as_bytes();
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let mut parser = Utf8Parser::new();
+                black_box(bytes)
+                    .iter()
+                    .filter_map(|byte| parser.push(*byte).unwrap())
+                    .count()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn feed(c: &mut Criterion) {
+    let mut group = c.benchmark_group("feed");
+    for (name, text) in corpus() {
+        let bytes = text.as_bytes();
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let mut parser = Utf8Parser::new();
+                parser.feed(black_box(bytes)).map(Result::unwrap).count()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn core_from_utf8(c: &mut Criterion) {
+    let mut group = c.benchmark_group("core_from_utf8");
+    for (name, text) in corpus() {
+        let bytes = text.as_bytes();
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                core::str::from_utf8(black_box(bytes))
+                    .unwrap()
+                    .chars()
+                    .count()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, push_slice, push, feed, core_from_utf8);
+criterion_main!(benches);
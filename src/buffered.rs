@@ -0,0 +1,129 @@
+use crate::{Utf8Parser, Utf8ParserError};
+
+// The borrowed characters of a chunk, and the character completed by its start
+type Decoded<'a> = (&'a str, Option<char>);
+
+/// A streaming decoder that borrows as much of each chunk as it can
+///
+/// Each call to [BufferedDecoder::decode_chunk] returns the chunk's complete characters as a
+/// `&str` borrowed from the chunk, without copying. A character split between chunks is carried
+/// over, and returned on its own once the chunk that completes it arrives, since it can't be
+/// borrowed from either chunk.
+///
+/// This sits between decoding a character at a time with [Utf8Parser::push], and decoding into
+/// an owned buffer.
+///
+/// # Example
+/// ```
+/// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+/// use utf8_parser::{BufferedDecoder, Utf8ParserError};
+///
+/// let mut decoder = BufferedDecoder::new();
+/// assert_eq!(decoder.decode_chunk(&[b'a', 0xf0, 0x9f]), Ok(("a", None)));
+/// assert_eq!(decoder.decode_chunk(&[0x8e]), Ok(("", None)));
+/// assert_eq!(decoder.decode_chunk(&[0x84, b'b', b'c']), Ok(("bc", Some('🎄'))));
+/// assert_eq!(
+///     decoder.decode_chunk(&[b'd', 0xff]),
+///     Err((Utf8ParserError::InvalidByte(0xff), "d", None))
+/// );
+/// decoder.finish()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct BufferedDecoder {
+    parser: Utf8Parser,
+}
+
+impl BufferedDecoder {
+    /// Construct a new decoder
+    pub const fn new() -> Self {
+        Self {
+            parser: Utf8Parser::new(),
+        }
+    }
+
+    /// Decode the next chunk of input
+    ///
+    /// Returns the complete characters borrowed from `chunk`, and the character completed by
+    /// the start of `chunk`, if any. That character comes before the borrowed slice. A partial
+    /// sequence at the end of `chunk` is carried over to the next call.
+    ///
+    /// On error, the characters decoded before the error are returned along with it, the rest
+    /// of `chunk` is discarded, and the decoder is ready for the next chunk.
+    pub fn decode_chunk<'a>(
+        &mut self,
+        chunk: &'a [u8],
+    ) -> Result<Decoded<'a>, (Utf8ParserError, &'a str, Option<char>)> {
+        // Only the character before the slice is ever held back
+        match self.parser.push_chunk(chunk) {
+            Ok(decoded) => Ok((decoded, self.parser.pop_pending())),
+            Err((err, decoded)) => Err((err, decoded, self.parser.pop_pending())),
+        }
+    }
+
+    /// Signal the end of input
+    ///
+    /// Errors with [Utf8ParserError::UnexpectedEndOfInput] if a character was left incomplete.
+    pub fn finish(&mut self) -> Result<(), Utf8ParserError> {
+        self.parser.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn random_chunks() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            let original: String = (0..rng.gen_range(0..32))
+                .map(|_| rng.gen::<char>())
+                .collect();
+            let mut decoder = BufferedDecoder::new();
+            let mut decoded = String::new();
+            let mut bytes = original.as_bytes();
+            while !bytes.is_empty() {
+                let (chunk, rest) = bytes.split_at(rng.gen_range(0..=bytes.len().min(5)));
+                let (borrowed, boundary) = decoder.decode_chunk(chunk).unwrap();
+                decoded.extend(boundary);
+                decoded.push_str(borrowed);
+                bytes = rest;
+            }
+            decoder.finish().unwrap();
+            assert_eq!(decoded, original);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let mut decoder = BufferedDecoder::new();
+        assert_eq!(
+            decoder.decode_chunk(&[0xff, b'a']),
+            Err((Utf8ParserError::InvalidByte(0xff), "", None))
+        );
+        assert_eq!(decoder.decode_chunk(b"cd"), Ok(("cd", None)));
+
+        // Neither the prefix nor the boundary character is lost when the rest of the chunk is
+        // invalid
+        assert_eq!(
+            decoder.decode_chunk(&[b'a', b'b', 0xff, b'c']),
+            Err((Utf8ParserError::InvalidByte(0xff), "ab", None))
+        );
+        assert_eq!(decoder.decode_chunk(&[0xc3]), Ok(("", None)));
+        assert_eq!(
+            decoder.decode_chunk(&[0xa9, b'f', 0x80]),
+            Err((
+                Utf8ParserError::UnexpectedContinuationByte(0x80),
+                "f",
+                Some('é')
+            ))
+        );
+        assert_eq!(decoder.decode_chunk(b"e"), Ok(("e", None)));
+
+        assert_eq!(decoder.decode_chunk(&[0xe2, 0x82]), Ok(("", None)));
+        assert_eq!(decoder.finish(), Err(Utf8ParserError::UnexpectedEndOfInput));
+    }
+}
@@ -0,0 +1,238 @@
+use crate::{Config, Utf8Parser};
+
+/// Configures a [Utf8Parser] with any combination of modes
+///
+/// Every mode is off by default, so `Utf8ParserBuilder::new().build()` is equivalent to
+/// [Utf8Parser::new].
+///
+/// # Example
+/// ```
+/// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+/// use utf8_parser::Utf8Parser;
+///
+/// let mut parser = Utf8Parser::builder()
+///     .lenient(true)
+///     .skip_bom(true)
+///     .reject_noncharacters(true)
+///     .build();
+/// let decoded: String = parser
+///     .feed(&[0xef, 0xbb, 0xbf, b'a', 0xef, 0xbf, 0xbf])
+///     .collect::<Result<_, _>>()?;
+/// assert_eq!(decoded, "a\u{fffd}");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Utf8ParserBuilder {
+    config: Config,
+}
+
+impl Utf8ParserBuilder {
+    /// Construct a new builder, with every mode off
+    pub const fn new() -> Self {
+        Self {
+            config: Config::new(),
+        }
+    }
+
+    /// Replace malformed input with U+FFFD instead of erroring
+    ///
+    /// See [Lenient Mode](Utf8Parser#lenient-mode).
+    pub const fn lenient(mut self, enabled: bool) -> Self {
+        self.config.lenient = enabled;
+        self
+    }
+
+    /// Allow encoded surrogates
+    ///
+    /// See [WTF-8 Mode](Utf8Parser#wtf-8-mode).
+    pub const fn wtf8(mut self, enabled: bool) -> Self {
+        self.config.wtf8 = enabled;
+        self
+    }
+
+    /// Combine encoded surrogate pairs, and reject four-byte sequences
+    ///
+    /// See [CESU-8 Mode](Utf8Parser#cesu-8-mode).
+    pub const fn cesu8(mut self, enabled: bool) -> Self {
+        self.config.cesu8 = enabled;
+        self
+    }
+
+    /// Decode Java's Modified UTF-8, allowing 0xC0 0x80 for NUL
+    ///
+    /// Enabling this also enables [Utf8ParserBuilder::cesu8], as with [Utf8Parser::new_mutf8].
+    pub const fn mutf8(mut self, enabled: bool) -> Self {
+        self.config.mutf8 = enabled;
+        if enabled {
+            self.config.cesu8 = true;
+        }
+        self
+    }
+
+    /// Skip a leading byte order mark
+    ///
+    /// See [Utf8Parser::new_skip_bom].
+    pub const fn skip_bom(mut self, enabled: bool) -> Self {
+        self.config.skip_bom = enabled;
+        self
+    }
+
+    /// Error on noncharacters
+    ///
+    /// See [Utf8Parser::new_reject_noncharacters].
+    pub const fn reject_noncharacters(mut self, enabled: bool) -> Self {
+        self.config.reject_noncharacters = enabled;
+        self
+    }
+
+    /// Error on control characters other than tab, line feed, and carriage return
+    ///
+    /// See [Utf8Parser::new_reject_controls].
+    pub const fn reject_controls(mut self, enabled: bool) -> Self {
+        self.config.reject_controls = enabled;
+        self
+    }
+
+    /// Yield `\r\n` as a single `\n`
+    ///
+    /// See [CRLF Mode](Utf8Parser#crlf-mode).
+    pub const fn coalesce_crlf(mut self, enabled: bool) -> Self {
+        self.config.coalesce_crlf = enabled;
+        self
+    }
+
+    /// Decode overlong encodings instead of erroring
+    ///
+    /// See [Overlong Mode](Utf8Parser#overlong-mode). **This is a security risk for untrusted
+    /// input.**
+    pub const fn allow_overlong(mut self, enabled: bool) -> Self {
+        self.config.allow_overlong = enabled;
+        self
+    }
+
+    /// Error on code points above `max`
+    ///
+    /// See [Utf8Parser::new_bmp_only]. By default, every code point up to U+10FFFF is accepted.
+    pub const fn max_scalar(mut self, max: u32) -> Self {
+        self.config.max_scalar = max;
+        self
+    }
+
+    /// Error on start bytes of sequences longer than `len` bytes
+    ///
+    /// The start byte itself errors with [Utf8ParserError::SequenceTooLong], rather than
+    /// waiting for the sequence to complete. By default, sequences of up to 4 bytes are
+    /// accepted.
+    ///
+    /// [Utf8ParserError::SequenceTooLong]: crate::Utf8ParserError::SequenceTooLong
+    ///
+    /// # Panics
+    /// Panics if `len` isn't between 1 and 4.
+    pub const fn max_sequence_len(mut self, len: u8) -> Self {
+        assert!(
+            len >= 1 && len <= 4,
+            "sequence length must be between 1 and 4"
+        );
+        self.config.max_sequence_len = len;
+        self
+    }
+
+    /// Error on 0xC0, 0xC1, and 0xF5 to 0xF7 as soon as they're pushed
+    ///
+    /// 0xC0 and 0xC1 can only start overlong two-byte sequences, and 0xF5 to 0xF7 can only
+    /// start code points above U+10FFFF, so validators following RFC 3629 reject them outright.
+    /// They error with [Utf8ParserError::InvalidByte], instead of
+    /// [Utf8ParserError::OverlongEncoding] or [Utf8ParserError::InvalidChar] once the sequence
+    /// completes. This is off by default, to keep reporting the more descriptive errors.
+    ///
+    /// In MUTF-8 mode, 0xC0 is still allowed, to encode NUL. This takes precedence over
+    /// [Utf8ParserBuilder::allow_overlong].
+    ///
+    /// [Utf8ParserError::InvalidByte]: crate::Utf8ParserError::InvalidByte
+    /// [Utf8ParserError::OverlongEncoding]: crate::Utf8ParserError::OverlongEncoding
+    /// [Utf8ParserError::InvalidChar]: crate::Utf8ParserError::InvalidChar
+    pub const fn strict_start_bytes(mut self, enabled: bool) -> Self {
+        self.config.strict_start_bytes = enabled;
+        self
+    }
+
+    /// Construct a [Utf8Parser] with the configured modes
+    ///
+    /// # Panics
+    /// Panics if both [WTF-8 mode](Utf8Parser#wtf-8-mode) and
+    /// [CRLF mode](Utf8Parser#crlf-mode) are enabled, since a surrogate can't be held behind a
+    /// carriage return.
+    pub const fn build(self) -> Utf8Parser {
+        assert!(
+            !(self.config.wtf8 && self.config.coalesce_crlf),
+            "WTF-8 mode can't be combined with CRLF mode"
+        );
+        Utf8Parser::with_config(self.config)
+    }
+}
+
+impl Default for Utf8ParserBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Utf8ParserError;
+
+    fn decode(parser: &mut Utf8Parser, bytes: &[u8]) -> Result<String, Utf8ParserError> {
+        let decoded = parser.feed(bytes).collect::<Result<_, _>>()?;
+        parser.finish()?;
+        Ok(decoded)
+    }
+
+    #[test]
+    fn default_matches_new() {
+        let mut built = Utf8ParserBuilder::new().build();
+        let mut parser = Utf8Parser::new();
+        for byte in [
+            b'a', 0xef, 0xbb, 0xbf, 0xc0, 0x80, 0xed, 0xa0, 0x80, 0xff, 0xf4, 0x90,
+        ] {
+            assert_eq!(built.push(byte), parser.push(byte));
+        }
+        assert_eq!(built.finish(), parser.finish());
+    }
+
+    #[test]
+    fn combined_modes() {
+        let mut parser = Utf8Parser::builder()
+            .mutf8(true)
+            .max_scalar(0xffff)
+            .reject_controls(true)
+            .build();
+        // NUL is a control character
+        assert_eq!(
+            decode(&mut parser, &[0xc0, 0x80]),
+            Err(Utf8ParserError::ControlCharacter(0))
+        );
+        // U+1F384, as a CESU-8 surrogate pair
+        assert_eq!(
+            decode(&mut parser, &[0xed, 0xa0, 0xbc, 0xed, 0xbe, 0x84]),
+            Err(Utf8ParserError::CodePointTooLarge(0x1f384))
+        );
+
+        let mut parser = Utf8Parser::builder()
+            .lenient(true)
+            .reject_noncharacters(true)
+            .skip_bom(true)
+            .build();
+        assert_eq!(
+            decode(&mut parser, "\u{feff}a\u{fffe}\u{feff}".as_bytes()),
+            Ok("a\u{fffd}\u{feff}".into())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "WTF-8 mode can't be combined with CRLF mode")]
+    fn wtf8_rejects_crlf() {
+        Utf8Parser::builder().wtf8(true).coalesce_crlf(true).build();
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{Utf8Parser, Utf8ParserError};
+
+/// A fixed-capacity buffer of decoded characters
+///
+/// # Example
+/// ```
+/// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+/// use utf8_parser::{CharBuf, Utf8ParserError};
+///
+/// let mut buf = CharBuf::<2>::new();
+/// for byte in "é🎄".bytes() {
+///     buf.push_byte(byte)?;
+/// }
+/// assert_eq!(buf.as_slice(), ['é', '🎄']);
+/// assert_eq!(buf.push_byte(b'a'), Err(Utf8ParserError::OutputBufferFull));
+/// buf.clear();
+/// buf.push_byte(b'a')?;
+/// assert_eq!(buf.as_slice(), ['a']);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct CharBuf<const N: usize> {
+    chars: [char; N],
+    len: usize,
+    parser: Utf8Parser,
+}
+
+impl<const N: usize> CharBuf<N> {
+    /// Construct a new, empty buffer
+    pub const fn new() -> Self {
+        Self {
+            chars: ['\0'; N],
+            len: 0,
+            parser: Utf8Parser::new(),
+        }
+    }
+
+    /// Push a byte into the buffer's parser, storing the character if one is completed
+    ///
+    /// Errors with [Utf8ParserError::OutputBufferFull] if a character is completed but there's
+    /// no room for it.
+    pub fn push_byte(&mut self, byte: u8) -> Result<(), Utf8ParserError> {
+        if let Some(c) = self.parser.push(byte)? {
+            self.store(c)?;
+        }
+        while let Some(c) = self.parser.pop_pending() {
+            self.store(c)?;
+        }
+        Ok(())
+    }
+
+    /// The characters decoded so far
+    pub fn as_slice(&self) -> &[char] {
+        &self.chars[..self.len]
+    }
+
+    /// Remove all decoded characters
+    ///
+    /// This doesn't affect a partially-decoded character.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    fn store(&mut self, c: char) -> Result<(), Utf8ParserError> {
+        let slot = self
+            .chars
+            .get_mut(self.len)
+            .ok_or(Utf8ParserError::OutputBufferFull)?;
+        *slot = c;
+        self.len += 1;
+        Ok(())
+    }
+}
+
+impl<const N: usize> Default for CharBuf<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_buffer() -> Result<(), Utf8ParserError> {
+        let original = "Thé quick brown 🦊";
+        let mut buf = CharBuf::<17>::new();
+        for byte in original.bytes() {
+            buf.push_byte(byte)?;
+        }
+        assert_eq!(buf.as_slice().iter().collect::<String>(), original);
+
+        // A partial character survives a full buffer, and clearing
+        buf.push_byte(0xc3)?;
+        buf.clear();
+        buf.push_byte(0xa9)?;
+        assert_eq!(buf.as_slice(), ['é']);
+        Ok(())
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let mut buf = CharBuf::<0>::new();
+        assert_eq!(buf.push_byte(0xc3), Ok(()));
+        assert_eq!(buf.push_byte(0xa9), Err(Utf8ParserError::OutputBufferFull));
+        assert!(buf.as_slice().is_empty());
+    }
+}
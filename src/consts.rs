@@ -0,0 +1,40 @@
+//! The code point boundaries between UTF-8 sequence lengths
+//!
+//! These are the same thresholds the parser uses to detect overlong encodings: a sequence that
+//! encodes a code point below its length's boundary is rejected with
+//! [OverlongEncoding](crate::Utf8ParserError::OverlongEncoding).
+//!
+//! # Example
+//! ```
+//! use utf8_parser::consts::{FIRST_CODE_POINT_FOR_QUADRUPLE, FIRST_CODE_POINT_FOR_TRIPLE};
+//!
+//! let needs_three_bytes = |c: char| {
+//!     (FIRST_CODE_POINT_FOR_TRIPLE..FIRST_CODE_POINT_FOR_QUADRUPLE).contains(&(c as u32))
+//! };
+//! assert!(needs_three_bytes('€'));
+//! assert!(!needs_three_bytes('🦊'));
+//! ```
+
+/// The smallest code point encoded with two bytes, U+0080
+pub const FIRST_CODE_POINT_FOR_DOUBLE: u32 = 0x80;
+/// The smallest code point encoded with three bytes, U+0800
+pub const FIRST_CODE_POINT_FOR_TRIPLE: u32 = 0x800;
+/// The smallest code point encoded with four bytes, U+10000
+pub const FIRST_CODE_POINT_FOR_QUADRUPLE: u32 = 0x10000;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boundaries_match_len_utf8() {
+        for (boundary, len) in [
+            (FIRST_CODE_POINT_FOR_DOUBLE, 2),
+            (FIRST_CODE_POINT_FOR_TRIPLE, 3),
+            (FIRST_CODE_POINT_FOR_QUADRUPLE, 4),
+        ] {
+            assert_eq!(char::from_u32(boundary - 1).unwrap().len_utf8(), len - 1);
+            assert_eq!(char::from_u32(boundary).unwrap().len_utf8(), len);
+        }
+    }
+}
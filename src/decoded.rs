@@ -0,0 +1,125 @@
+use crate::{Utf8Parser, Utf8ParserError};
+use alloc::string::String;
+use core::{fmt, ops::Deref};
+
+/// A string that was decoded by a [Utf8Parser], including the check for truncated input
+///
+/// # Example
+/// ```
+/// use utf8_parser::{DecodedString, Utf8ParserError};
+///
+/// let decoded = DecodedString::try_from("h🎄".as_bytes()).unwrap();
+/// assert_eq!(&*decoded, "h🎄");
+/// assert_eq!(decoded.len(), 5);
+/// assert_eq!(
+///     DecodedString::try_from(&[0xf0, 0x9f][..]),
+///     Err(Utf8ParserError::UnexpectedEndOfInput)
+/// );
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash, Default)]
+pub struct DecodedString(String);
+
+impl DecodedString {
+    /// Decode an iterator of bytes
+    ///
+    /// This is [DecodedString::try_from] for input that isn't already in a slice.
+    ///
+    /// # Example
+    /// ```
+    /// use utf8_parser::{DecodedString, Utf8ParserError};
+    ///
+    /// let bytes = "h🎄".bytes();
+    /// assert_eq!(&*DecodedString::try_from_bytes(bytes.clone()).unwrap(), "h🎄");
+    /// assert_eq!(
+    ///     DecodedString::try_from_bytes(bytes.take(3)),
+    ///     Err(Utf8ParserError::UnexpectedEndOfInput)
+    /// );
+    /// ```
+    pub fn try_from_bytes<I: IntoIterator<Item = u8>>(bytes: I) -> Result<Self, Utf8ParserError> {
+        // Truncated input is reported as the final item
+        crate::decode_utf8(bytes)
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    /// Convert into the underlying [String]
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl TryFrom<&[u8]> for DecodedString {
+    type Error = Utf8ParserError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Utf8Parser::new().decode_all(bytes).map(Self)
+    }
+}
+
+impl Deref for DecodedString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for DecodedString {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<DecodedString> for String {
+    fn from(decoded: DecodedString) -> Self {
+        decoded.0
+    }
+}
+
+impl fmt::Display for DecodedString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_from_bytes() {
+        let original = "Thé quick brown 🦊";
+        let decoded = DecodedString::try_from(original.as_bytes()).unwrap();
+        assert_eq!(decoded.as_ref(), original);
+        assert_eq!(decoded.to_string(), original);
+        assert!(decoded.starts_with("Thé"));
+        assert_eq!(String::from(decoded), original);
+
+        assert_eq!(
+            DecodedString::try_from(&b"ab\xffc"[..]),
+            Err(Utf8ParserError::InvalidByte(0xff))
+        );
+        assert_eq!(*DecodedString::try_from(&[][..]).unwrap(), *"");
+    }
+
+    #[test]
+    fn collect_from_byte_iterator() {
+        let original = "Thé quick brown 🦊";
+        let bytes = original.bytes().filter(|byte| *byte != b' ');
+        let decoded = DecodedString::try_from_bytes(bytes).unwrap();
+        assert_eq!(decoded.as_ref(), "Théquickbrown🦊");
+        assert_eq!(*DecodedString::try_from_bytes([]).unwrap(), *"");
+
+        assert_eq!(
+            DecodedString::try_from_bytes(b"ab\xffc".iter().copied()),
+            Err(Utf8ParserError::InvalidByte(0xff))
+        );
+        for len in 1..4 {
+            let truncated = "🦊".bytes().take(len);
+            assert_eq!(
+                DecodedString::try_from_bytes(truncated),
+                Err(Utf8ParserError::UnexpectedEndOfInput)
+            );
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use crate::{
+    is_surrogate, Utf8ByteType, Utf8ParserError, FIRST_CODE_POINT_FOR_DOUBLE,
+    FIRST_CODE_POINT_FOR_QUADRUPLE, FIRST_CODE_POINT_FOR_TRIPLE,
+};
+
+/// Number of bytes needed to encode a character as UTF-8
+///
+/// # Example
+/// ```
+/// use utf8_parser::encoded_len;
+///
+/// assert_eq!(encoded_len('a'), 1);
+/// assert_eq!(encoded_len('é'), 2);
+/// assert_eq!(encoded_len('€'), 3);
+/// assert_eq!(encoded_len('🎄'), 4);
+/// ```
+pub const fn encoded_len(c: char) -> usize {
+    let c = c as u32;
+    if c < FIRST_CODE_POINT_FOR_DOUBLE {
+        1
+    } else if c < FIRST_CODE_POINT_FOR_TRIPLE {
+        2
+    } else if c < FIRST_CODE_POINT_FOR_QUADRUPLE {
+        3
+    } else {
+        4
+    }
+}
+
+/// The type of start byte that the UTF-8 encoding of a code point begins with
+///
+/// Errors with [Utf8ParserError::SurrogateCodePoint] for surrogates, and
+/// [Utf8ParserError::InvalidChar] for values above U+10FFFF, since neither can be encoded.
+/// The start byte is followed by [Utf8ByteType::continuation_count] continuation bytes.
+///
+/// # Example
+/// ```
+/// use utf8_parser::{classify_scalar, Utf8ByteType, Utf8ParserError};
+///
+/// assert_eq!(classify_scalar(0x61), Ok(Utf8ByteType::Single));
+/// assert_eq!(classify_scalar(0x20ac), Ok(Utf8ByteType::Triple));
+/// assert_eq!(classify_scalar(0xd800), Err(Utf8ParserError::SurrogateCodePoint(0xd800)));
+/// assert_eq!(classify_scalar(0x110000), Err(Utf8ParserError::InvalidChar(0x110000)));
+/// ```
+pub const fn classify_scalar(cp: u32) -> Result<Utf8ByteType, Utf8ParserError> {
+    if is_surrogate(cp) {
+        Err(Utf8ParserError::SurrogateCodePoint(cp))
+    } else if cp > char::MAX as u32 {
+        Err(Utf8ParserError::InvalidChar(cp))
+    } else if cp < FIRST_CODE_POINT_FOR_DOUBLE {
+        Ok(Utf8ByteType::Single)
+    } else if cp < FIRST_CODE_POINT_FOR_TRIPLE {
+        Ok(Utf8ByteType::Double)
+    } else if cp < FIRST_CODE_POINT_FOR_QUADRUPLE {
+        Ok(Utf8ByteType::Triple)
+    } else {
+        Ok(Utf8ByteType::Quadruple)
+    }
+}
+
+/// Encode a character as UTF-8, returning the populated part of `out`
+///
+/// # Example
+/// ```
+/// use utf8_parser::encode_char;
+///
+/// let mut buffer = [0; 4];
+/// assert_eq!(encode_char('🎄', &mut buffer), [0xf0, 0x9f, 0x8e, 0x84]);
+/// ```
+pub fn encode_char(c: char, out: &mut [u8; 4]) -> &[u8] {
+    let len = encoded_len(c);
+    encode_prefix(c as u32, len, len, out)
+}
+
+// Encode the first `seen` bytes of a `len`-byte sequence, where `value` holds the payload bits of
+// just those bytes
+pub(crate) fn encode_prefix(mut value: u32, len: usize, seen: usize, out: &mut [u8; 4]) -> &[u8] {
+    let start_type = match len {
+        1 => Utf8ByteType::Single,
+        2 => Utf8ByteType::Double,
+        3 => Utf8ByteType::Triple,
+        _ => Utf8ByteType::Quadruple,
+    };
+    let shift = Utf8ByteType::Continuation.payload_bits();
+
+    // Fill continuation bytes from the back
+    for byte in out[1..seen].iter_mut().rev() {
+        *byte = (Utf8ByteType::Continuation.id() << shift)
+            | (value as u8 & Utf8ByteType::Continuation.value_mask());
+        value >>= shift;
+    }
+
+    out[0] = (start_type.id() << start_type.payload_bits()) | value as u8;
+
+    &out[..seen]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Utf8Parser;
+
+    #[test]
+    fn encode_matches_core() {
+        for c in [
+            '\0',
+            'a',
+            '\u{7f}',
+            '\u{80}',
+            'é',
+            '\u{7ff}',
+            '\u{800}',
+            '€',
+            '\u{ffff}',
+            '\u{10000}',
+            '🦊',
+            char::MAX,
+        ] {
+            let mut ours = [0; 4];
+            let mut theirs = [0; 4];
+            assert_eq!(
+                encode_char(c, &mut ours),
+                c.encode_utf8(&mut theirs).as_bytes()
+            );
+            assert_eq!(encoded_len(c), c.len_utf8());
+        }
+    }
+
+    #[test]
+    fn classify_scalar_matches_start_byte() -> Result<(), Utf8ParserError> {
+        for cp in (0..=0x11_0000).step_by(0x7f) {
+            match char::from_u32(cp) {
+                Some(c) => {
+                    let mut buffer = [0; 4];
+                    let start = encode_char(c, &mut buffer)[0];
+                    assert_eq!(classify_scalar(cp)?, Utf8ByteType::of(start)?);
+                }
+                None => assert!(classify_scalar(cp).is_err()),
+            }
+        }
+        assert_eq!(classify_scalar(0x7ff), Ok(Utf8ByteType::Double));
+        assert_eq!(classify_scalar(0x800), Ok(Utf8ByteType::Triple));
+        assert_eq!(
+            classify_scalar(0xdfff),
+            Err(Utf8ParserError::SurrogateCodePoint(0xdfff))
+        );
+        assert_eq!(classify_scalar(0x10ffff), Ok(Utf8ByteType::Quadruple));
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip() -> Result<(), crate::Utf8ParserError> {
+        let mut parser = Utf8Parser::new();
+        for c in "Thé quick brown 🦊 jamped over the lazy 🐕".chars() {
+            let mut buffer = [0; 4];
+            let bytes = encode_char(c, &mut buffer);
+            let (last, init) = bytes.split_last().unwrap();
+            for byte in init {
+                assert_eq!(parser.push(*byte)?, None);
+            }
+            assert_eq!(parser.push(*last)?, Some(c));
+        }
+        Ok(())
+    }
+}
@@ -1,12 +1,48 @@
 use core::fmt;
 
 /// Error type used for the `utf8-parser` crate
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+///
+/// Errors are ordered by variant, in the order they're declared, and then by payload.
+///
+/// # Malformed Input
+///
+/// Each way a strict [Utf8Parser](crate::Utf8Parser) can reject input maps to one variant. The
+/// error is returned by the byte that makes the input invalid.
+///
+/// | Scenario                                               | Example        | Variant                                        |
+/// |--------------------------------------------------------|----------------|------------------------------------------------|
+/// | A byte that never appears in UTF-8 (0xF8 to 0xFF)      | `FF`           | [InvalidByte](Self::InvalidByte)               |
+/// | A continuation byte where a start byte was expected    | `80`           | [UnexpectedContinuationByte](Self::UnexpectedContinuationByte) |
+/// | A start byte, including ASCII, partway through a sequence | `C3 41`     | [UnexpectedStartByte](Self::UnexpectedStartByte) |
+/// | Input ending partway through a sequence                | `C3`, then [finish](crate::Utf8Parser::finish) | [UnexpectedEndOfInput](Self::UnexpectedEndOfInput) |
+/// | A longer sequence than the code point needs            | `C0 80`, `E0 80` | [OverlongEncoding](Self::OverlongEncoding)   |
+/// | An encoded surrogate                                   | `ED A0 80`     | [SurrogateCodePoint](Self::SurrogateCodePoint) |
+/// | A code point above U+10FFFF                            | `F4 90 80 80`  | [InvalidChar](Self::InvalidChar)               |
+///
+/// The byte payloads of [InvalidByte](Self::InvalidByte),
+/// [UnexpectedStartByte](Self::UnexpectedStartByte),
+/// [UnexpectedContinuationByte](Self::UnexpectedContinuationByte) and
+/// [SequenceTooLong](Self::SequenceTooLong) are the offending byte as it
+/// appeared in the input.
+///
+/// More variants may be added in the future, so matches should include a catch-all arm. Use
+/// [Utf8ParserError::is_recoverable] to decide how to handle variants you don't know about.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum Utf8ParserError {
     /// Encountered an invalid byte. This is a byte that's invalid no matter the context.
     InvalidByte(u8),
     /// Found a code point that's not valid UTF-8.
     InvalidChar(u32),
+    /// Found a UTF-16 surrogate code point (U+D800 to U+DFFF), which can't be encoded in UTF-8
+    SurrogateCodePoint(u32),
+    /// Found a noncharacter, when configured to reject them
+    Noncharacter(u32),
+    /// Found a code point larger than the parser is configured to accept
+    CodePointTooLarge(u32),
+    /// Found a control character, when configured to reject them
+    ControlCharacter(u32),
     /// Found a start byte in an unexpected place
     UnexpectedStartByte(u8),
     /// Found a continuation byte in an unexpected place
@@ -21,6 +57,144 @@ pub enum Utf8ParserError {
     /// > that there is a unique valid encoding for each code point. This ensures that string
     /// > comparisons and searches are well-defined.
     OverlongEncoding,
+    /// Found a start byte for a sequence longer than the parser is configured to accept
+    SequenceTooLong(u8),
+    /// The output buffer was not large enough to hold the decoded characters
+    OutputBufferFull,
+    /// Input ended in the middle of a multi-byte sequence
+    UnexpectedEndOfInput,
+}
+
+impl Utf8ParserError {
+    /// The category of this error, without its payload
+    ///
+    /// # Example
+    /// ```
+    /// use utf8_parser::{Utf8ErrorKind, Utf8ParserError};
+    ///
+    /// let err = Utf8ParserError::InvalidByte(0xff);
+    /// assert_eq!(err.kind(), Utf8ErrorKind::InvalidByte);
+    /// ```
+    pub const fn kind(&self) -> Utf8ErrorKind {
+        match self {
+            Self::InvalidByte(_) => Utf8ErrorKind::InvalidByte,
+            Self::InvalidChar(_) => Utf8ErrorKind::InvalidChar,
+            Self::SurrogateCodePoint(_) => Utf8ErrorKind::SurrogateCodePoint,
+            Self::Noncharacter(_) => Utf8ErrorKind::Noncharacter,
+            Self::CodePointTooLarge(_) => Utf8ErrorKind::CodePointTooLarge,
+            Self::ControlCharacter(_) => Utf8ErrorKind::ControlCharacter,
+            Self::UnexpectedStartByte(_) => Utf8ErrorKind::UnexpectedStartByte,
+            Self::UnexpectedContinuationByte(_) => Utf8ErrorKind::UnexpectedContinuationByte,
+            Self::OverlongEncoding => Utf8ErrorKind::OverlongEncoding,
+            Self::SequenceTooLong(_) => Utf8ErrorKind::SequenceTooLong,
+            Self::OutputBufferFull => Utf8ErrorKind::OutputBufferFull,
+            Self::UnexpectedEndOfInput => Utf8ErrorKind::UnexpectedEndOfInput,
+        }
+    }
+
+    /// A static description of this error, without its payload
+    ///
+    /// This is the [Display](fmt::Display) message up to the payload, for logging without
+    /// `core::fmt`.
+    ///
+    /// # Example
+    /// ```
+    /// use utf8_parser::Utf8ParserError;
+    ///
+    /// let err = Utf8ParserError::InvalidByte(0xff);
+    /// assert_eq!(err.as_str(), "Found invalid byte");
+    /// ```
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::InvalidByte(_) => "Found invalid byte",
+            Self::InvalidChar(_) => "Parsed invalid UTF-8 code point",
+            Self::SurrogateCodePoint(_) => "Parsed surrogate code point",
+            Self::Noncharacter(_) => "Parsed noncharacter",
+            Self::CodePointTooLarge(_) => "Parsed code point above the configured maximum",
+            Self::ControlCharacter(_) => "Parsed control character",
+            Self::UnexpectedStartByte(_) => {
+                "Found start byte when a continuation byte was expected"
+            }
+            Self::UnexpectedContinuationByte(_) => {
+                "Found continuation byte when a start byte was expected"
+            }
+            Self::OverlongEncoding => "Found overlong encoding",
+            Self::SequenceTooLong(_) => {
+                "Found start byte of a sequence longer than the configured maximum"
+            }
+            Self::OutputBufferFull => "Output buffer is full",
+            Self::UnexpectedEndOfInput => "Input ended in the middle of a multi-byte sequence",
+        }
+    }
+
+    /// Whether the parser that returned this error has reset and can keep accepting bytes
+    ///
+    /// This is currently true of every variant.
+    ///
+    /// # Example
+    /// ```
+    /// use utf8_parser::Utf8Parser;
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// let err = parser.push(0xff).unwrap_err();
+    /// assert!(err.is_recoverable());
+    /// assert_eq!(parser.push(b'a'), Ok(Some('a')));
+    /// ```
+    pub const fn is_recoverable(&self) -> bool {
+        match self {
+            Self::InvalidByte(_)
+            | Self::InvalidChar(_)
+            | Self::SurrogateCodePoint(_)
+            | Self::Noncharacter(_)
+            | Self::CodePointTooLarge(_)
+            | Self::ControlCharacter(_)
+            | Self::UnexpectedStartByte(_)
+            | Self::UnexpectedContinuationByte(_)
+            | Self::OverlongEncoding
+            | Self::SequenceTooLong(_)
+            | Self::OutputBufferFull
+            | Self::UnexpectedEndOfInput => true,
+        }
+    }
+
+    /// Whether the parser that returned this error can no longer be used
+    ///
+    /// The opposite of [Utf8ParserError::is_recoverable].
+    pub const fn is_fatal(&self) -> bool {
+        !self.is_recoverable()
+    }
+}
+
+/// The category of a [Utf8ParserError], without its payload
+///
+/// Each variant corresponds to the [Utf8ParserError] variant of the same name.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+#[non_exhaustive]
+pub enum Utf8ErrorKind {
+    /// See [Utf8ParserError::InvalidByte]
+    InvalidByte,
+    /// See [Utf8ParserError::InvalidChar]
+    InvalidChar,
+    /// See [Utf8ParserError::SurrogateCodePoint]
+    SurrogateCodePoint,
+    /// See [Utf8ParserError::Noncharacter]
+    Noncharacter,
+    /// See [Utf8ParserError::CodePointTooLarge]
+    CodePointTooLarge,
+    /// See [Utf8ParserError::ControlCharacter]
+    ControlCharacter,
+    /// See [Utf8ParserError::UnexpectedStartByte]
+    UnexpectedStartByte,
+    /// See [Utf8ParserError::UnexpectedContinuationByte]
+    UnexpectedContinuationByte,
+    /// See [Utf8ParserError::OverlongEncoding]
+    OverlongEncoding,
+    /// See [Utf8ParserError::SequenceTooLong]
+    SequenceTooLong,
+    /// See [Utf8ParserError::OutputBufferFull]
+    OutputBufferFull,
+    /// See [Utf8ParserError::UnexpectedEndOfInput]
+    UnexpectedEndOfInput,
 }
 
 impl fmt::Display for Utf8ParserError {
@@ -32,6 +206,21 @@ impl fmt::Display for Utf8ParserError {
             Self::InvalidChar(word) => {
                 write!(f, "Parsed invalid UTF-8 code point: 0x{word:04x}")
             }
+            Self::SurrogateCodePoint(word) => {
+                write!(f, "Parsed surrogate code point: 0x{word:04x}")
+            }
+            Self::Noncharacter(word) => {
+                write!(f, "Parsed noncharacter: 0x{word:04x}")
+            }
+            Self::CodePointTooLarge(word) => {
+                write!(
+                    f,
+                    "Parsed code point above the configured maximum: 0x{word:04x}"
+                )
+            }
+            Self::ControlCharacter(word) => {
+                write!(f, "Parsed control character: 0x{word:04x}")
+            }
             Self::UnexpectedStartByte(byte) => {
                 write!(
                     f,
@@ -47,8 +236,221 @@ impl fmt::Display for Utf8ParserError {
             Self::OverlongEncoding => {
                 write!(f, "Found overlong encoding")
             }
+            Self::SequenceTooLong(byte) => {
+                write!(
+                    f,
+                    "Found start byte of a sequence longer than the configured maximum: 0x{byte:02x}"
+                )
+            }
+            Self::OutputBufferFull => {
+                write!(f, "Output buffer is full")
+            }
+            Self::UnexpectedEndOfInput => {
+                write!(f, "Input ended in the middle of a multi-byte sequence")
+            }
         }
     }
 }
 
 impl core::error::Error for Utf8ParserError {}
+
+/// Error type returned by [from_utf8](crate::from_utf8), mirroring [core::str::Utf8Error]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Utf8Error {
+    valid_up_to: usize,
+    error_len: Option<u8>,
+}
+
+impl Utf8Error {
+    pub(crate) const fn new(valid_up_to: usize, error_len: Option<u8>) -> Self {
+        Self {
+            valid_up_to,
+            error_len,
+        }
+    }
+
+    /// Length of the longest prefix of the input that is valid UTF-8
+    ///
+    /// See [core::str::Utf8Error::valid_up_to].
+    pub const fn valid_up_to(&self) -> usize {
+        self.valid_up_to
+    }
+
+    /// Number of invalid bytes to skip after [Utf8Error::valid_up_to], or `None` if the input
+    /// ended partway through a character
+    ///
+    /// See [core::str::Utf8Error::error_len].
+    pub const fn error_len(&self) -> Option<usize> {
+        match self.error_len {
+            Some(len) => Some(len as usize),
+            None => None,
+        }
+    }
+}
+
+impl From<core::str::Utf8Error> for Utf8Error {
+    fn from(err: core::str::Utf8Error) -> Self {
+        Self::new(err.valid_up_to(), err.error_len().map(|len| len as u8))
+    }
+}
+
+impl fmt::Display for Utf8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.error_len {
+            Some(len) => write!(
+                f,
+                "Found {len} invalid byte(s) after {} valid byte(s)",
+                self.valid_up_to
+            ),
+            None => write!(
+                f,
+                "Input ended in the middle of a multi-byte sequence after {} valid byte(s)",
+                self.valid_up_to
+            ),
+        }
+    }
+}
+
+impl core::error::Error for Utf8Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_and_kind() {
+        let cases = [
+            (
+                Utf8ParserError::InvalidByte(0xff),
+                Utf8ErrorKind::InvalidByte,
+                "Found invalid byte: 0xff",
+            ),
+            (
+                Utf8ParserError::InvalidChar(0x110000),
+                Utf8ErrorKind::InvalidChar,
+                "Parsed invalid UTF-8 code point: 0x110000",
+            ),
+            (
+                Utf8ParserError::SurrogateCodePoint(0xd800),
+                Utf8ErrorKind::SurrogateCodePoint,
+                "Parsed surrogate code point: 0xd800",
+            ),
+            (
+                Utf8ParserError::Noncharacter(0xfffe),
+                Utf8ErrorKind::Noncharacter,
+                "Parsed noncharacter: 0xfffe",
+            ),
+            (
+                Utf8ParserError::CodePointTooLarge(0x10000),
+                Utf8ErrorKind::CodePointTooLarge,
+                "Parsed code point above the configured maximum: 0x10000",
+            ),
+            (
+                Utf8ParserError::ControlCharacter(0x7f),
+                Utf8ErrorKind::ControlCharacter,
+                "Parsed control character: 0x007f",
+            ),
+            (
+                Utf8ParserError::UnexpectedStartByte(0x61),
+                Utf8ErrorKind::UnexpectedStartByte,
+                "Found start byte when a continuation byte was expected: 0x61",
+            ),
+            (
+                Utf8ParserError::UnexpectedContinuationByte(0x01),
+                Utf8ErrorKind::UnexpectedContinuationByte,
+                "Found continuation byte when a start byte was expected: 0x01",
+            ),
+            (
+                Utf8ParserError::OverlongEncoding,
+                Utf8ErrorKind::OverlongEncoding,
+                "Found overlong encoding",
+            ),
+            (
+                Utf8ParserError::SequenceTooLong(0xf0),
+                Utf8ErrorKind::SequenceTooLong,
+                "Found start byte of a sequence longer than the configured maximum: 0xf0",
+            ),
+            (
+                Utf8ParserError::OutputBufferFull,
+                Utf8ErrorKind::OutputBufferFull,
+                "Output buffer is full",
+            ),
+            (
+                Utf8ParserError::UnexpectedEndOfInput,
+                Utf8ErrorKind::UnexpectedEndOfInput,
+                "Input ended in the middle of a multi-byte sequence",
+            ),
+        ];
+
+        for (err, kind, message) in cases {
+            assert_eq!(err.kind(), kind);
+            assert_eq!(err.to_string(), message);
+            assert!(message.starts_with(err.as_str()), "{err:?}");
+        }
+    }
+
+    #[test]
+    fn malformed_input_table() {
+        for (bytes, expected) in [
+            (&[0xff][..], Utf8ParserError::InvalidByte(0xff)),
+            (&[0x80], Utf8ParserError::UnexpectedContinuationByte(0x80)),
+            (&[0xc3, 0x41], Utf8ParserError::UnexpectedStartByte(0x41)),
+            (&[0xc3, 0xc3], Utf8ParserError::UnexpectedStartByte(0xc3)),
+            (&[0xc0, 0x80], Utf8ParserError::OverlongEncoding),
+            (&[0xe0, 0x80], Utf8ParserError::OverlongEncoding),
+            (
+                &[0xed, 0xa0, 0x80],
+                Utf8ParserError::SurrogateCodePoint(0xd800),
+            ),
+            (
+                &[0xf4, 0x90, 0x80, 0x80],
+                Utf8ParserError::InvalidChar(0x110000),
+            ),
+        ] {
+            let mut parser = crate::Utf8Parser::new();
+            let (last, init) = bytes.split_last().unwrap();
+            for byte in init {
+                assert_eq!(parser.push(*byte), Ok(None));
+            }
+            assert_eq!(parser.push(*last), Err(expected));
+        }
+
+        let mut parser = crate::Utf8Parser::new();
+        parser.push(0xc3).unwrap();
+        assert_eq!(parser.finish(), Err(Utf8ParserError::UnexpectedEndOfInput));
+    }
+
+    #[test]
+    fn all_recoverable() {
+        let mut parser = crate::Utf8Parser::new_reject_noncharacters();
+        for bytes in [
+            &[0xff][..],
+            &[0x80],
+            &[0xc3, 0x41],
+            &[0xc0, 0x80],
+            &[0xed, 0xa0, 0x80],
+            &[0xef, 0xbf, 0xbe],
+            &[0xf4, 0x90, 0x80, 0x80],
+        ] {
+            let err = parser.feed(bytes).find_map(Result::err).unwrap();
+            assert!(err.is_recoverable());
+            assert!(!err.is_fatal());
+            assert!(parser.is_idle());
+        }
+        assert!(Utf8ParserError::UnexpectedEndOfInput.is_recoverable());
+        assert!(Utf8ParserError::OutputBufferFull.is_recoverable());
+        assert!(Utf8ParserError::SequenceTooLong(0xf0).is_recoverable());
+    }
+
+    #[test]
+    fn kind_ignores_payload() {
+        assert_eq!(
+            Utf8ParserError::InvalidByte(0xff).kind(),
+            Utf8ParserError::InvalidByte(0xfe).kind()
+        );
+        assert_ne!(
+            Utf8ParserError::InvalidByte(0xff),
+            Utf8ParserError::InvalidByte(0xfe)
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{Utf8Parser, Utf8ParserError};
+use core::fmt;
+
+/// A [fmt::Write] implementation that pushes written strings through a parser, passing each
+/// character to a callback
+///
+/// This makes `write!` a source of bytes to decode. Written strings are always valid UTF-8, so
+/// this is mostly useful with a parser mode that validates or transforms its input, such as
+/// [CRLF Mode](Utf8Parser#crlf-mode).
+///
+/// A parser error results in [fmt::Error]. The underlying [Utf8ParserError] can be retrieved
+/// with [Utf8FmtAdapter::error].
+///
+/// # Example
+/// ```
+/// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+/// use core::fmt::Write;
+/// use utf8_parser::{Utf8FmtAdapter, Utf8Parser};
+///
+/// let mut decoded = String::new();
+/// let mut adapter =
+///     Utf8FmtAdapter::with_parser(Utf8Parser::new_coalesce_crlf(), |c| decoded.push(c));
+/// write!(adapter, "{}\r\n{}\r", 1, 2).unwrap();
+/// adapter.finish()?;
+/// assert_eq!(decoded, "1\n2\r");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Utf8FmtAdapter<F> {
+    parser: Utf8Parser,
+    callback: F,
+    error: Option<Utf8ParserError>,
+}
+
+impl<F: FnMut(char)> Utf8FmtAdapter<F> {
+    /// Construct a new adapter that calls `callback` with each decoded character
+    pub const fn new(callback: F) -> Self {
+        Self::with_parser(Utf8Parser::new(), callback)
+    }
+
+    /// Construct a new adapter that decodes with `parser`, and calls `callback` with each
+    /// decoded character
+    pub const fn with_parser(parser: Utf8Parser, callback: F) -> Self {
+        Self {
+            parser,
+            callback,
+            error: None,
+        }
+    }
+
+    /// The error that caused the last [fmt::Error], if any
+    pub const fn error(&self) -> Option<Utf8ParserError> {
+        self.error
+    }
+
+    /// Signal the end of input, passing any character held back by the parser to the callback
+    pub fn finish(&mut self) -> Result<(), Utf8ParserError> {
+        self.parser.finish()?;
+        while let Some(c) = self.parser.pop_pending() {
+            (self.callback)(c);
+        }
+        Ok(())
+    }
+}
+
+impl<F: FnMut(char)> fmt::Write for Utf8FmtAdapter<F> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in self.parser.feed(s.as_bytes()) {
+            match c {
+                Ok(c) => (self.callback)(c),
+                Err(err) => {
+                    self.error = Some(err);
+                    return Err(fmt::Error);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::fmt::Write;
+
+    #[test]
+    fn write_formatted() {
+        let mut decoded = Vec::new();
+        let mut adapter = Utf8FmtAdapter::new(|c| decoded.push(c));
+        let (c, n) = ('é', 7);
+        write!(adapter, "{c}🦊{n:>3}").unwrap();
+        adapter.finish().unwrap();
+        assert_eq!(decoded, ['é', '🦊', ' ', ' ', '7']);
+    }
+
+    #[test]
+    fn parser_errors() {
+        let mut decoded = String::new();
+        let mut adapter =
+            Utf8FmtAdapter::with_parser(Utf8Parser::new_bmp_only(), |c| decoded.push(c));
+        assert_eq!(adapter.error(), None);
+        assert_eq!(write!(adapter, "a🦊b"), Err(fmt::Error));
+        assert_eq!(
+            adapter.error(),
+            Some(Utf8ParserError::CodePointTooLarge(0x1f98a))
+        );
+        assert_eq!(decoded, "a");
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{is_rejected_control, Utf8Parser, Utf8ParserError, Utf8ParserState};
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+// Number of noncharacters: U+FDD0 to U+FDEF, and the last two code points of each plane
+const NONCHARACTER_COUNT: usize = 32 + 17 * 2;
+
+/// Generates errors a parser could actually return, so each payload is consistent with its
+/// variant. For example, [Utf8ParserError::UnexpectedContinuationByte] always holds a
+/// continuation byte, and [Utf8ParserError::SurrogateCodePoint] always holds a surrogate.
+impl<'a> Arbitrary<'a> for Utf8ParserError {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.choose_index(12)? {
+            0 => Self::InvalidByte(*u.choose(&[0xc0, 0xc1, 0xf5, 0xf6, 0xf7, 0xf8, 0xff])?),
+            1 => Self::InvalidChar(u.int_in_range(0x110000..=0x1fffff)?),
+            2 => Self::SurrogateCodePoint(u.int_in_range(0xd800..=0xdfff)?),
+            3 => {
+                let index = u.choose_index(NONCHARACTER_COUNT)? as u32;
+                Self::Noncharacter(match index.checked_sub(32) {
+                    None => 0xfdd0 + index,
+                    Some(index) => (index / 2) << 16 | 0xfffe | (index & 1),
+                })
+            }
+            // No parser can be configured to reject U+0000 as too large
+            4 => Self::CodePointTooLarge((u.arbitrary::<char>()? as u32).max(1)),
+            5 => {
+                let controls = (0..=0x9f).filter(|val| is_rejected_control(*val));
+                let index = u.choose_index(controls.clone().count())?;
+                Self::ControlCharacter(controls.into_iter().nth(index).unwrap_or(0))
+            }
+            6 => Self::UnexpectedStartByte(match u.int_in_range(0x00..=0xb7)? {
+                byte @ 0x00..=0x7f => byte,
+                byte => byte + 0x40,
+            }),
+            7 => Self::UnexpectedContinuationByte(u.int_in_range(0x80..=0xbf)?),
+            8 => Self::OverlongEncoding,
+            9 => Self::SequenceTooLong(u.int_in_range(0xc0..=0xf7)?),
+            10 => Self::OutputBufferFull,
+            _ => Self::UnexpectedEndOfInput,
+        })
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        (1, Some(5))
+    }
+}
+
+/// Generates states by configuring a parser with arbitrary modes and pushing arbitrary bytes
+/// into it, so every generated state is one a real parser can reach.
+impl<'a> Arbitrary<'a> for Utf8ParserState {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let wtf8 = u.arbitrary()?;
+        let mut parser = Utf8Parser::builder()
+            .lenient(u.arbitrary()?)
+            .wtf8(wtf8)
+            .cesu8(u.arbitrary()?)
+            .mutf8(u.arbitrary()?)
+            .skip_bom(u.arbitrary()?)
+            .reject_noncharacters(u.arbitrary()?)
+            .reject_controls(u.arbitrary()?)
+            .coalesce_crlf(!wtf8 && u.arbitrary()?)
+            .allow_overlong(u.arbitrary()?)
+            .strict_start_bytes(u.arbitrary()?)
+            .max_scalar(u.int_in_range(0..=char::MAX as u32)?)
+            .max_sequence_len(u.int_in_range(1..=4)?)
+            .build();
+        let bytes: &[u8] = u.arbitrary()?;
+        for byte in bytes {
+            // Characters from the last push are left pending, as a caller would see them
+            while parser.pop_pending().is_some() {}
+            // Errors reset the parser, which is just another reachable state
+            let _ = parser.push_wtf8(*byte);
+        }
+        Ok(parser.save_state())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{is_noncharacter, Utf8ByteType};
+    use rand::Rng;
+
+    fn random_data() -> Vec<u8> {
+        let mut rng = rand::thread_rng();
+        (0..rng.gen_range(0..64)).map(|_| rng.gen()).collect()
+    }
+
+    #[test]
+    fn errors_are_consistent() {
+        for _ in 0..10_000 {
+            let data = random_data();
+            let err = Utf8ParserError::arbitrary(&mut Unstructured::new(&data)).unwrap();
+            let consistent = match err {
+                Utf8ParserError::InvalidByte(byte) => matches!(byte, 0xc0 | 0xc1 | 0xf5..),
+                Utf8ParserError::InvalidChar(val) => val > char::MAX as u32,
+                Utf8ParserError::SurrogateCodePoint(val) => (0xd800..=0xdfff).contains(&val),
+                Utf8ParserError::Noncharacter(val) => is_noncharacter(val),
+                Utf8ParserError::CodePointTooLarge(val) => val > 0 && char::from_u32(val).is_some(),
+                Utf8ParserError::ControlCharacter(val) => is_rejected_control(val),
+                Utf8ParserError::UnexpectedStartByte(byte) => {
+                    Utf8ByteType::of(byte).is_ok_and(Utf8ByteType::is_start)
+                }
+                Utf8ParserError::UnexpectedContinuationByte(byte) => {
+                    Utf8ByteType::of(byte) == Ok(Utf8ByteType::Continuation)
+                }
+                Utf8ParserError::SequenceTooLong(byte) => {
+                    Utf8ByteType::of(byte).is_ok_and(Utf8ByteType::is_multibyte)
+                }
+                _ => true,
+            };
+            assert!(consistent, "{err:?}");
+        }
+    }
+
+    #[test]
+    fn states_are_reachable() {
+        for _ in 0..10_000 {
+            let data = random_data();
+            let state = Utf8ParserState::arbitrary(&mut Unstructured::new(&data)).unwrap();
+            let mut parser = Utf8Parser::from_state(state);
+            assert_eq!(parser.save_state(), state);
+            for byte in random_data() {
+                while parser.pop_pending().is_some() {}
+                let _ = parser.push_wtf8(byte);
+            }
+        }
+    }
+}
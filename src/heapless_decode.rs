@@ -0,0 +1,88 @@
+use crate::{Utf8Parser, Utf8ParserError};
+
+/// Decode `bytes`, appending each character to a [heapless::String]
+///
+/// Errors with [Utf8ParserError::OutputBufferFull] if `out` runs out of room, or
+/// [Utf8ParserError::UnexpectedEndOfInput] if `bytes` ends partway through a character. On error,
+/// `out` keeps the characters decoded before it.
+///
+/// # Example
+/// ```
+/// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+/// use utf8_parser::{decode_into_heapless, Utf8ParserError};
+///
+/// let mut out = heapless::String::<4>::new();
+/// decode_into_heapless("aé".as_bytes(), &mut out)?;
+/// assert_eq!(out, "aé");
+/// assert_eq!(
+///     decode_into_heapless("🦊".as_bytes(), &mut out),
+///     Err(Utf8ParserError::OutputBufferFull)
+/// );
+/// assert_eq!(out, "aé");
+/// # Ok(())
+/// # }
+/// ```
+pub fn decode_into_heapless<const N: usize>(
+    bytes: &[u8],
+    out: &mut heapless::String<N>,
+) -> Result<(), Utf8ParserError> {
+    decode_with(bytes, |c| out.push(c))
+}
+
+/// Decode `bytes`, appending each character to a [heapless::Vec]
+///
+/// This behaves like [decode_into_heapless], but `N` counts characters rather than bytes.
+pub fn decode_into_heapless_vec<const N: usize>(
+    bytes: &[u8],
+    out: &mut heapless::Vec<char, N>,
+) -> Result<(), Utf8ParserError> {
+    decode_with(bytes, |c| out.push(c).map_err(|_| ()))
+}
+
+fn decode_with(
+    bytes: &[u8],
+    mut store: impl FnMut(char) -> Result<(), ()>,
+) -> Result<(), Utf8ParserError> {
+    let mut parser = Utf8Parser::new();
+    for c in parser.feed(bytes) {
+        store(c?).map_err(|()| Utf8ParserError::OutputBufferFull)?;
+    }
+    parser.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_to_capacity() {
+        let mut out = heapless::String::<7>::new();
+        assert_eq!(decode_into_heapless("é🦊a".as_bytes(), &mut out), Ok(()));
+        assert_eq!(out, "é🦊a");
+        assert_eq!(
+            decode_into_heapless(b"b", &mut out),
+            Err(Utf8ParserError::OutputBufferFull)
+        );
+
+        let mut out = heapless::Vec::<char, 2>::new();
+        assert_eq!(
+            decode_into_heapless_vec("é🦊a".as_bytes(), &mut out),
+            Err(Utf8ParserError::OutputBufferFull)
+        );
+        assert_eq!(out, ['é', '🦊']);
+    }
+
+    #[test]
+    fn malformed_input() {
+        let mut out = heapless::String::<8>::new();
+        assert_eq!(
+            decode_into_heapless(&[b'a', 0xff], &mut out),
+            Err(Utf8ParserError::InvalidByte(0xff))
+        );
+        assert_eq!(
+            decode_into_heapless(&[b'b', 0xe2, 0x82], &mut out),
+            Err(Utf8ParserError::UnexpectedEndOfInput)
+        );
+        assert_eq!(out, "ab");
+    }
+}
@@ -0,0 +1,68 @@
+// Number of bytes kept by `ByteHistory`
+const HISTORY_LEN: usize = 8;
+
+// The most recent bytes pushed into a parser, oldest first
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct ByteHistory {
+    bytes: [u8; HISTORY_LEN],
+    len: usize,
+}
+
+impl ByteHistory {
+    pub(crate) const fn new() -> Self {
+        Self {
+            bytes: [0; HISTORY_LEN],
+            len: 0,
+        }
+    }
+
+    pub(crate) fn record(&mut self, bytes: &[u8]) {
+        let bytes = &bytes[bytes.len().saturating_sub(HISTORY_LEN)..];
+        let keep = self.len.min(HISTORY_LEN - bytes.len());
+        self.bytes.copy_within(self.len - keep..self.len, 0);
+        self.bytes[keep..keep + bytes.len()].copy_from_slice(bytes);
+        self.len = keep + bytes.len();
+    }
+
+    pub(crate) fn as_slice(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Utf8Parser;
+
+    #[test]
+    fn keeps_last_bytes() {
+        let mut parser = Utf8Parser::new();
+        assert!(parser.recent_bytes().is_empty());
+        let _ = parser.feed(&[b'a', 0xc3]).count();
+        assert_eq!(parser.recent_bytes(), [b'a', 0xc3]);
+        let _ = parser.feed(&[b'b', 0xff, 0x80, b'c', b'd', b'e']).count();
+        assert_eq!(
+            parser.recent_bytes(),
+            [b'a', 0xc3, b'b', 0xff, 0x80, b'c', b'd', b'e']
+        );
+        let _ = parser.feed(b"f").count();
+        assert_eq!(
+            parser.recent_bytes(),
+            [0xc3, b'b', 0xff, 0x80, b'c', b'd', b'e', b'f']
+        );
+    }
+
+    #[test]
+    fn bulk_paths_record_bytes() {
+        let text = "Thé quick brown 🦊 jamped";
+        let mut out = ['\0'; 32];
+        let mut parser = Utf8Parser::new();
+        parser.push_slice(text.as_bytes(), &mut out).unwrap();
+        assert_eq!(parser.recent_bytes(), &text.as_bytes()[text.len() - 8..]);
+
+        let mut parser = Utf8Parser::new();
+        parser.push_chunk(&text.as_bytes()[..5]).unwrap();
+        assert_eq!(parser.recent_bytes(), &text.as_bytes()[..5]);
+        parser.push_chunk(&text.as_bytes()[5..]).unwrap();
+        assert_eq!(parser.recent_bytes(), &text.as_bytes()[text.len() - 8..]);
+    }
+}
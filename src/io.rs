@@ -0,0 +1,402 @@
+use crate::{Utf8Parser, Utf8ParserError};
+use core::fmt;
+use std::io;
+
+// Size of the chunks read by `ReaderChars`
+const READ_CHUNK_SIZE: usize = 256;
+
+/// Wraps the error in an [io::Error] of kind [io::ErrorKind::InvalidData]
+///
+/// The original error can be recovered with [io::Error::get_ref] and `downcast_ref`, or with
+/// [io::Error::into_inner] and `downcast`.
+impl From<Utf8ParserError> for io::Error {
+    fn from(err: Utf8ParserError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// An [io::Write] implementation that decodes written bytes, passing each character to a callback
+///
+/// Malformed input results in an [io::Error] of kind [io::ErrorKind::InvalidData] that wraps the
+/// [Utf8ParserError]. As [io::Write] requires, a write that errors consumes nothing: the bytes
+/// before the malformed one are reported as written, and the next write starts with it and
+/// errors.
+///
+/// # Example
+/// ```
+/// # fn main() -> std::io::Result<()> {
+/// use std::io::Write;
+/// use utf8_parser::Utf8WriteAdapter;
+///
+/// let mut decoded = String::new();
+/// let mut adapter = Utf8WriteAdapter::new(|c| decoded.push(c));
+/// adapter.write_all("h🎄".as_bytes())?;
+/// adapter.finish()?;
+/// assert_eq!(decoded, "h🎄");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Utf8WriteAdapter<F> {
+    parser: Utf8Parser,
+    callback: F,
+}
+
+impl<F: FnMut(char)> Utf8WriteAdapter<F> {
+    /// Construct a new adapter that calls `callback` with each decoded character
+    pub const fn new(callback: F) -> Self {
+        Self {
+            parser: Utf8Parser::new(),
+            callback,
+        }
+    }
+
+    /// Signal the end of input, erroring if it ended partway through a character
+    pub fn finish(&mut self) -> io::Result<()> {
+        Ok(self.parser.finish()?)
+    }
+}
+
+impl<F: FnMut(char)> io::Write for Utf8WriteAdapter<F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut written = 0;
+        for byte in buf {
+            // Only the first byte is pushed for good if it errors, so any other malformed byte is
+            // left for the next write
+            let result = match written {
+                0 => self.parser.push(*byte),
+                _ => self.parser.try_push(*byte),
+            };
+            match result {
+                Ok(c) => c.into_iter().for_each(&mut self.callback),
+                Err(_) if written > 0 => break,
+                Err(err) => return Err(err.into()),
+            }
+            written += 1;
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Error type for [ReaderChars]
+#[derive(Debug)]
+pub enum Utf8ReadError {
+    /// Reading from the underlying reader failed
+    Io(io::Error),
+    /// The bytes read were not valid UTF-8
+    Utf8(Utf8ParserError),
+}
+
+impl fmt::Display for Utf8ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "Failed to read: {err}"),
+            Self::Utf8(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Utf8ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Utf8(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for Utf8ReadError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<Utf8ParserError> for Utf8ReadError {
+    fn from(err: Utf8ParserError) -> Self {
+        Self::Utf8(err)
+    }
+}
+
+/// An iterator that decodes characters from an [io::Read]
+///
+/// Created by [from_reader].
+#[derive(Debug)]
+pub struct ReaderChars<R> {
+    reader: R,
+    parser: Utf8Parser,
+    buffer: [u8; READ_CHUNK_SIZE],
+    // Read position within `buffer`
+    pos: usize,
+    // Number of valid bytes in `buffer`
+    len: usize,
+}
+
+/// Decode characters from an [io::Read]
+///
+/// The reader is read in chunks, so wrapping it in a [BufReader](io::BufReader) is unnecessary.
+/// If the reader runs out partway through a multi-byte character, the final item is
+/// [Utf8ParserError::UnexpectedEndOfInput].
+///
+/// # Example
+/// ```
+/// # fn main() -> Result<(), utf8_parser::Utf8ReadError> {
+/// use utf8_parser::from_reader;
+///
+/// let reader = "h🎄".as_bytes();
+/// let decoded: String = from_reader(reader).collect::<Result<_, _>>()?;
+/// assert_eq!(decoded, "h🎄");
+/// # Ok(())
+/// # }
+/// ```
+pub fn from_reader<R: io::Read>(reader: R) -> ReaderChars<R> {
+    ReaderChars {
+        reader,
+        parser: Utf8Parser::new(),
+        buffer: [0; READ_CHUNK_SIZE],
+        pos: 0,
+        len: 0,
+    }
+}
+
+impl<R: io::Read> Iterator for ReaderChars<R> {
+    type Item = Result<char, Utf8ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while self.pos < self.len {
+                let byte = self.buffer[self.pos];
+                self.pos += 1;
+                if let Some(result) = self.parser.push(byte).transpose() {
+                    return Some(result.map_err(Into::into));
+                }
+            }
+
+            match self.reader.read(&mut self.buffer) {
+                Ok(0) => {
+                    return self.parser.finish().err().map(|err| Err(err.into()));
+                }
+                Ok(len) => {
+                    self.pos = 0;
+                    self.len = len;
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    return Some(Err(err.into()));
+                }
+            }
+        }
+    }
+}
+
+/// An iterator that decodes characters from an [io::BufRead]
+///
+/// Unlike [from_reader], this reads straight from the reader's own buffer. One parser is kept
+/// across buffer refills, so a character split between two fills is decoded correctly.
+///
+/// # Example
+/// ```
+/// # fn main() -> Result<(), utf8_parser::Utf8ReadError> {
+/// use std::io::BufReader;
+/// use utf8_parser::BufReadDecoder;
+///
+/// // A tiny buffer, so '🎄' is split across refills
+/// let reader = BufReader::with_capacity(2, "h🎄".as_bytes());
+/// let decoded: String = BufReadDecoder::new(reader).collect::<Result<_, _>>()?;
+/// assert_eq!(decoded, "h🎄");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct BufReadDecoder<R> {
+    reader: R,
+    parser: Utf8Parser,
+}
+
+impl<R: io::BufRead> BufReadDecoder<R> {
+    /// Construct a new decoder that reads from `reader`
+    pub const fn new(reader: R) -> Self {
+        Self {
+            reader,
+            parser: Utf8Parser::new(),
+        }
+    }
+
+    /// Get a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Get a mutable reference to the underlying reader
+    ///
+    /// Reading from it directly skips those bytes, even if it's partway through a character.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Convert into the underlying reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: io::BufRead> Iterator for BufReadDecoder<R> {
+    type Item = Result<char, Utf8ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let buffer = match self.reader.fill_buf() {
+                Ok(buffer) => buffer,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Some(Err(err.into())),
+            };
+            if buffer.is_empty() {
+                return self.parser.finish().err().map(|err| Err(err.into()));
+            }
+
+            let mut used = 0;
+            let mut result = None;
+            for byte in buffer {
+                used += 1;
+                result = self.parser.push(*byte).transpose();
+                if result.is_some() {
+                    break;
+                }
+            }
+            self.reader.consume(used);
+
+            if let Some(result) = result {
+                return Some(result.map_err(Into::into));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    // Reader that returns at most `chunk` bytes per read
+    struct Trickle<'a> {
+        bytes: &'a [u8],
+        chunk: usize,
+    }
+
+    impl io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = self.chunk.min(buf.len()).min(self.bytes.len());
+            buf[..len].copy_from_slice(&self.bytes[..len]);
+            self.bytes = &self.bytes[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn copy_into_adapter() -> io::Result<()> {
+        let original = "Thé quick brown 🦊 jamped over the lazy 🐕";
+        let mut decoded = String::new();
+        let mut adapter = Utf8WriteAdapter::new(|c| decoded.push(c));
+        // Small chunks make sure sequences get split across writes
+        for chunk in original.as_bytes().chunks(3) {
+            adapter.write_all(chunk)?;
+        }
+        adapter.finish()?;
+        assert_eq!(decoded, original);
+        Ok(())
+    }
+
+    #[test]
+    fn buf_read_across_refills() {
+        let original = "Thé quick brown 🦊 jamped over the lazy 🐕";
+        for capacity in 1..8 {
+            let reader = io::BufReader::with_capacity(
+                capacity,
+                Trickle {
+                    bytes: original.as_bytes(),
+                    chunk: capacity,
+                },
+            );
+            let decoded: String = BufReadDecoder::new(reader).map(Result::unwrap).collect();
+            assert_eq!(decoded, original);
+        }
+
+        // Errors don't stop decoding, and truncation is reported at the end
+        let reader = io::BufReader::with_capacity(2, &b"a\xffb\xf0\x9f"[..]);
+        let decoded: Vec<_> = BufReadDecoder::new(reader)
+            .map(|result| result.map_err(|err| err.to_string()))
+            .collect();
+        assert_eq!(
+            decoded,
+            [
+                Ok('a'),
+                Err(Utf8ParserError::InvalidByte(0xff).to_string()),
+                Ok('b'),
+                Err(Utf8ParserError::UnexpectedEndOfInput.to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_across_boundaries() {
+        let original = "Thé quick brown 🦊 jamped over the lazy 🐕";
+        for chunk in 1..8 {
+            let reader = Trickle {
+                bytes: original.as_bytes(),
+                chunk,
+            };
+            let decoded: String = from_reader(reader).map(Result::unwrap).collect();
+            assert_eq!(decoded, original);
+        }
+    }
+
+    #[test]
+    fn read_truncated() {
+        let mut chars = from_reader(&[b'a', 0xe2, 0x82][..]);
+        assert_eq!(chars.next().unwrap().unwrap(), 'a');
+        assert!(matches!(
+            chars.next(),
+            Some(Err(Utf8ReadError::Utf8(
+                Utf8ParserError::UnexpectedEndOfInput
+            )))
+        ));
+        assert!(chars.next().is_none());
+    }
+
+    #[test]
+    fn invalid_data() {
+        let mut decoded = String::new();
+        let mut adapter = Utf8WriteAdapter::new(|c| decoded.push(c));
+        // The bytes before the error are written, and the error is left for the next write
+        assert_eq!(adapter.write(&[b'a', 0xc3, 0xff, b'b']).unwrap(), 2);
+        let err = adapter.write(&[0xff, b'b']).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(adapter.write(b"b").unwrap(), 1);
+        let err = adapter.write_all(&[b'c', 0xff]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        adapter.write_all(&[0xc3]).unwrap();
+        let err = adapter.finish().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(decoded, "abc");
+    }
+
+    #[test]
+    fn downcast_io_error() {
+        let mut adapter = Utf8WriteAdapter::new(|_| {});
+        let err = adapter.write_all(&[b'a', 0xff]).unwrap_err();
+        assert_eq!(
+            err.get_ref()
+                .and_then(|inner| inner.downcast_ref::<Utf8ParserError>()),
+            Some(&Utf8ParserError::InvalidByte(0xff))
+        );
+        assert_eq!(
+            err.into_inner()
+                .and_then(|inner| inner.downcast::<Utf8ParserError>().ok()),
+            Some(Box::new(Utf8ParserError::InvalidByte(0xff)))
+        );
+    }
+}
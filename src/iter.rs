@@ -0,0 +1,347 @@
+use crate::{Utf8Parser, Utf8ParserError};
+#[cfg(feature = "alloc")]
+use alloc::vec::{self, Vec};
+
+/// An iterator that decodes UTF-8 from an iterator of bytes
+///
+/// Created by [decode_utf8].
+#[derive(Clone, Debug)]
+pub struct DecodeUtf8<I> {
+    iter: I,
+    parser: Utf8Parser,
+}
+
+/// Decode UTF-8 from an iterator of bytes
+///
+/// If the bytes run out partway through a multi-byte character, the final item is
+/// [Utf8ParserError::UnexpectedEndOfInput].
+///
+/// # Example
+/// ```
+/// use utf8_parser::{decode_utf8, Utf8ParserError};
+///
+/// let mut chars = decode_utf8([b'a', 0xc3, 0xa9, 0xc3]);
+/// assert_eq!(chars.next(), Some(Ok('a')));
+/// assert_eq!(chars.next(), Some(Ok('é')));
+/// assert_eq!(chars.next(), Some(Err(Utf8ParserError::UnexpectedEndOfInput)));
+/// assert_eq!(chars.next(), None);
+/// ```
+///
+/// Since truncation is reported as an item, collecting into a `Result` catches every error:
+///
+/// ```
+/// use utf8_parser::{decode_utf8, Utf8ParserError};
+///
+/// let decoded: Result<String, _> = decode_utf8("h🎄".bytes()).collect();
+/// assert_eq!(decoded.unwrap(), "h🎄");
+/// let decoded: Result<String, _> = decode_utf8("h🎄".bytes().take(3)).collect();
+/// assert_eq!(decoded, Err(Utf8ParserError::UnexpectedEndOfInput));
+/// ```
+pub fn decode_utf8<I: IntoIterator<Item = u8>>(iter: I) -> DecodeUtf8<I::IntoIter> {
+    DecodeUtf8 {
+        iter: iter.into_iter(),
+        parser: Utf8Parser::new(),
+    }
+}
+
+impl<I: Iterator<Item = u8>> Iterator for DecodeUtf8<I> {
+    type Item = Result<char, Utf8ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for byte in self.iter.by_ref() {
+            if let Some(result) = self.parser.push(byte).transpose() {
+                return Some(result);
+            }
+        }
+
+        self.parser.finish().err().map(Err)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Every byte yields at most one item, plus a possible truncation error
+        let (_, upper) = self.iter.size_hint();
+        (0, upper.and_then(|upper| upper.checked_add(1)))
+    }
+}
+
+/// An iterator over the characters of a byte slice, and their byte offsets
+///
+/// Created by [char_indices].
+#[derive(Clone, Debug)]
+pub struct CharIndices<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    // Offset of the first byte of the current character
+    start: usize,
+    parser: Utf8Parser,
+}
+
+/// Decode a byte slice, yielding each character with the offset of its first byte
+///
+/// This is the byte slice equivalent of [str::char_indices]. As with [decode_utf8], if the
+/// bytes run out partway through a character, the final item is
+/// [Utf8ParserError::UnexpectedEndOfInput].
+///
+/// # Example
+/// ```
+/// use utf8_parser::{char_indices, Utf8ParserError};
+///
+/// let mut chars = char_indices(&[b'a', 0xc3, 0xa9, 0xff, b'b']);
+/// assert_eq!(chars.next(), Some(Ok((0, 'a'))));
+/// assert_eq!(chars.next(), Some(Ok((1, 'é'))));
+/// assert_eq!(chars.next(), Some(Err(Utf8ParserError::InvalidByte(0xff))));
+/// assert_eq!(chars.next(), Some(Ok((4, 'b'))));
+/// assert_eq!(chars.next(), None);
+/// ```
+pub fn char_indices(bytes: &[u8]) -> CharIndices<'_> {
+    CharIndices {
+        bytes,
+        offset: 0,
+        start: 0,
+        parser: Utf8Parser::new(),
+    }
+}
+
+impl Iterator for CharIndices<'_> {
+    type Item = Result<(usize, char), Utf8ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(byte) = self.bytes.get(self.offset) {
+            if self.parser.is_idle() {
+                self.start = self.offset;
+            }
+            self.offset += 1;
+            if let Some(result) = self.parser.push(*byte).transpose() {
+                return Some(result.map(|c| (self.start, c)));
+            }
+        }
+
+        self.parser.finish().err().map(Err)
+    }
+}
+
+/// An iterator that decodes UTF-8 from an iterator of bytes, using a borrowed [Utf8Parser]
+///
+/// Created by [Utf8Parser::decoding].
+#[derive(Debug)]
+pub struct Decoding<'a, I> {
+    parser: &'a mut Utf8Parser,
+    iter: I,
+}
+
+impl Utf8Parser {
+    /// Decode UTF-8 from an iterator of bytes, using this parser
+    ///
+    /// Unlike [decode_utf8], this doesn't call [Utf8Parser::finish] when the bytes run out, so a
+    /// partial character carries over to the next use of the parser.
+    ///
+    /// # Example
+    /// ```
+    /// use utf8_parser::Utf8Parser;
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// let bytes = "h🎄".as_bytes();
+    /// let decoded: Result<String, _> = parser.decoding(bytes[..3].iter().copied()).collect();
+    /// assert_eq!(decoded.unwrap(), "h");
+    /// assert_eq!(parser.position(), 3);
+    /// let decoded: Result<String, _> = parser.decoding(bytes[3..].iter().copied()).collect();
+    /// assert_eq!(decoded.unwrap(), "🎄");
+    /// ```
+    pub fn decoding<I: IntoIterator<Item = u8>>(&mut self, iter: I) -> Decoding<'_, I::IntoIter> {
+        Decoding {
+            parser: self,
+            iter: iter.into_iter(),
+        }
+    }
+}
+
+impl<I: Iterator<Item = u8>> Iterator for Decoding<'_, I> {
+    type Item = Result<char, Utf8ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(c) = self.parser.pop_pending() {
+            return Some(Ok(c));
+        }
+
+        for byte in self.iter.by_ref() {
+            if let Some(result) = self.parser.push(byte).transpose() {
+                return Some(result);
+            }
+        }
+
+        None
+    }
+}
+
+/// An iterator that decodes an owned buffer of bytes, using an owned [Utf8Parser]
+///
+/// Created by [Utf8Parser::into_decoder].
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug)]
+pub struct IntoDecoder {
+    parser: Utf8Parser,
+    bytes: vec::IntoIter<u8>,
+}
+
+#[cfg(feature = "alloc")]
+impl Utf8Parser {
+    /// Decode an owned buffer of bytes, using this parser
+    ///
+    /// This is the owned counterpart to [Utf8Parser::feed], so the decoder can be returned or
+    /// passed around without keeping the buffer alive separately. Like [decode_utf8], if the
+    /// bytes run out partway through a character, the final item is
+    /// [Utf8ParserError::UnexpectedEndOfInput].
+    ///
+    /// # Example
+    /// ```
+    /// use utf8_parser::{IntoDecoder, Utf8Parser, Utf8ParserError};
+    ///
+    /// fn decoder() -> IntoDecoder {
+    ///     Utf8Parser::new().into_decoder(vec![b'a', 0xc3, 0xa9, 0xc3])
+    /// }
+    ///
+    /// let mut chars = decoder();
+    /// assert_eq!(chars.next(), Some(Ok('a')));
+    /// assert_eq!(chars.next(), Some(Ok('é')));
+    /// assert_eq!(chars.next(), Some(Err(Utf8ParserError::UnexpectedEndOfInput)));
+    /// assert_eq!(chars.next(), None);
+    /// ```
+    pub fn into_decoder(self, bytes: Vec<u8>) -> IntoDecoder {
+        IntoDecoder {
+            parser: self,
+            bytes: bytes.into_iter(),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Iterator for IntoDecoder {
+    type Item = Result<char, Utf8ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(c) = self.parser.pop_pending() {
+            return Some(Ok(c));
+        }
+
+        for byte in self.bytes.by_ref() {
+            if let Some(result) = self.parser.push(byte).transpose() {
+                return Some(result);
+            }
+        }
+
+        match self.parser.finish() {
+            Ok(()) => self.parser.pop_pending().map(Ok),
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_iterator() {
+        let original = "Thé quick brown 🦊 jamped over the lazy 🐕";
+        let rebuilt: String = decode_utf8(original.bytes()).map(Result::unwrap).collect();
+        assert_eq!(rebuilt, original);
+    }
+
+    #[test]
+    fn char_indices_matches_str() {
+        let original = "Thé quick brown 🦊 jamped over the lazy 🐕";
+        let ours: Result<Vec<_>, _> = char_indices(original.as_bytes()).collect();
+        assert_eq!(ours.unwrap(), original.char_indices().collect::<Vec<_>>());
+
+        let mut chars = char_indices(&[b'a', 0xc3, b'b', 0xf0, 0x9f]);
+        assert_eq!(chars.next(), Some(Ok((0, 'a'))));
+        assert_eq!(
+            chars.next(),
+            Some(Err(Utf8ParserError::UnexpectedStartByte(b'b')))
+        );
+        assert_eq!(
+            chars.next(),
+            Some(Err(Utf8ParserError::UnexpectedEndOfInput))
+        );
+        assert_eq!(chars.next(), None);
+    }
+
+    #[test]
+    fn decode_iterator_continues_after_error() {
+        let chars: Vec<_> = decode_utf8([0xff, b'a', 0x80, b'b']).collect();
+        assert_eq!(
+            chars,
+            [
+                Err(Utf8ParserError::InvalidByte(0xff)),
+                Ok('a'),
+                Err(Utf8ParserError::UnexpectedContinuationByte(0x80)),
+                Ok('b'),
+            ]
+        );
+    }
+
+    #[test]
+    fn collect_matches_from_utf8() {
+        let original = "Thé quick brown 🦊".as_bytes();
+        let mut cases: Vec<Vec<u8>> = (0..=original.len())
+            .map(|len| original[..len].to_vec())
+            .collect();
+        cases.push(vec![b'a', 0xff, b'b']);
+        cases.push(vec![0xed, 0xa0, 0x80]);
+
+        for case in cases {
+            let decoded: Result<String, _> = decode_utf8(case.iter().copied()).collect();
+            assert_eq!(
+                decoded.ok(),
+                String::from_utf8(case.clone()).ok(),
+                "{case:x?}"
+            );
+        }
+    }
+
+    #[test]
+    fn decoding_keeps_parser() {
+        let original = "Thé quick brown 🦊 jamped over the lazy 🐕";
+        let mut parser = Utf8Parser::new();
+        let mut decoded = String::new();
+        for chunk in original.as_bytes().chunks(3) {
+            decoded.extend(parser.decoding(chunk.iter().copied()).map(Result::unwrap));
+        }
+        assert_eq!(decoded, original);
+        assert_eq!(parser.position(), original.len() as u64);
+
+        // Held-back output from lenient mode is also yielded
+        let mut parser = Utf8Parser::new_lenient();
+        let decoded: Vec<_> = parser.decoding([0xe0, 0x80]).collect();
+        assert_eq!(decoded, [Ok('\u{fffd}'), Ok('\u{fffd}')]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn into_decoder_matches_feed() {
+        for (parser, bytes) in [
+            (Utf8Parser::new(), "Thé quick brown 🦊".as_bytes()),
+            (Utf8Parser::new(), &[b'a', 0xff, 0xf0, 0x9f]),
+            (Utf8Parser::new_lenient(), &[b'a', 0xe0, 0x80, 0xc3]),
+            (Utf8Parser::new_coalesce_crlf(), b"a\r\n\rb\r"),
+        ] {
+            let mut fed = parser.clone();
+            let mut expected: Vec<_> = fed.feed(bytes).collect();
+            expected.extend(fed.finish().err().map(Err));
+            expected.extend(fed.pop_pending().map(Ok));
+
+            let decoded: Vec<_> = parser.into_decoder(bytes.to_vec()).collect();
+            assert_eq!(decoded, expected, "{bytes:x?}");
+        }
+    }
+
+    #[test]
+    fn decode_iterator_truncated() {
+        let mut chars = decode_utf8("🦊".bytes().take(2));
+        assert_eq!(
+            chars.next(),
+            Some(Err(Utf8ParserError::UnexpectedEndOfInput))
+        );
+        assert_eq!(chars.next(), None);
+    }
+}
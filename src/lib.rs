@@ -7,12 +7,81 @@
 // Allowing arbitrary bit groupings makes readability easier in this context.
 #![allow(clippy::unusual_byte_groupings)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+mod buffered;
+mod builder;
+mod char_buf;
+pub mod consts;
+#[cfg(feature = "alloc")]
+mod decoded;
+mod encode;
 mod error;
-pub use error::Utf8ParserError;
+mod fmt_adapter;
+#[cfg(feature = "arbitrary")]
+mod fuzz;
+#[cfg(feature = "heapless")]
+mod heapless_decode;
+#[cfg(feature = "debug-history")]
+mod history;
+#[cfg(feature = "std")]
+mod io;
+mod iter;
+#[cfg(feature = "alloc")]
+mod lines;
+mod lossy;
+mod passthrough;
+mod pending;
+mod reframer;
+mod roles;
+mod slice;
+#[cfg(feature = "serde")]
+mod snapshot;
+#[cfg(feature = "stats")]
+mod stats;
+pub use buffered::BufferedDecoder;
+pub use builder::Utf8ParserBuilder;
+pub use char_buf::CharBuf;
+#[cfg(feature = "alloc")]
+pub use decoded::DecodedString;
+pub use encode::{classify_scalar, encode_char, encoded_len};
+pub use error::{Utf8Error, Utf8ErrorKind, Utf8ParserError};
+pub use fmt_adapter::Utf8FmtAdapter;
+#[cfg(feature = "heapless")]
+pub use heapless_decode::{decode_into_heapless, decode_into_heapless_vec};
+#[cfg(feature = "std")]
+pub use io::{from_reader, BufReadDecoder, ReaderChars, Utf8ReadError, Utf8WriteAdapter};
+#[cfg(feature = "alloc")]
+pub use iter::IntoDecoder;
+pub use iter::{char_indices, decode_utf8, CharIndices, DecodeUtf8, Decoding};
+#[cfg(feature = "alloc")]
+pub use lines::LineDecoder;
+pub use lossy::LossyParser;
+pub use passthrough::PassthroughParser;
+pub use reframer::Utf8Reframer;
+pub use roles::{byte_roles, ByteRole};
+pub use slice::{
+    classify, decode_slice, decode_str, decode_utf32, from_utf8, is_valid_utf8, next_char, resync,
+    split_at_last_complete, valid_prefix_len, validate, validate_const,
+};
+#[cfg(feature = "alloc")]
+pub use slice::{decode_cow, decode_with_offset};
+#[cfg(feature = "stats")]
+pub use stats::Utf8Stats;
+
+use consts::{
+    FIRST_CODE_POINT_FOR_DOUBLE, FIRST_CODE_POINT_FOR_QUADRUPLE, FIRST_CODE_POINT_FOR_TRIPLE,
+};
+use pending::{PendingChars, Span, SpannedChar, MAX_QUEUED};
 
-const FIRST_CODE_POINT_FOR_DOUBLE: u32 = 0x80;
-const FIRST_CODE_POINT_FOR_TRIPLE: u32 = 0x800;
-const FIRST_CODE_POINT_FOR_QUADRUPLE: u32 = 0x10000;
+const FIRST_SURROGATE: u32 = 0xD800;
+const LAST_HIGH_SURROGATE: u32 = 0xDBFF;
+const FIRST_LOW_SURROGATE: u32 = 0xDC00;
+const LAST_SURROGATE: u32 = 0xDFFF;
+const BYTE_ORDER_MARK: u32 = 0xFEFF;
 
 /// Categorization of a valid byte in UTF-8
 ///
@@ -31,6 +100,7 @@ const FIRST_CODE_POINT_FOR_QUADRUPLE: u32 = 0x10000;
 /// # }
 /// ```
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Utf8ByteType {
     /// A continuation byte
     Continuation,
@@ -66,6 +136,71 @@ impl Utf8ByteType {
         matches!(self, Self::Continuation)
     }
 
+    /// Returns true if this byte starts a character, including ASCII
+    ///
+    /// # Example
+    /// ```
+    /// use utf8_parser::Utf8ByteType;
+    ///
+    /// assert!(Utf8ByteType::Single.is_start());
+    /// assert!(Utf8ByteType::Triple.is_start());
+    /// assert!(!Utf8ByteType::Continuation.is_start());
+    /// ```
+    pub const fn is_start(self) -> bool {
+        !self.is_continuation()
+    }
+
+    /// Returns true if this is a one-byte character, i.e. ASCII
+    ///
+    /// # Example
+    /// ```
+    /// use utf8_parser::Utf8ByteType;
+    ///
+    /// assert!(Utf8ByteType::Single.is_single());
+    /// assert!(!Utf8ByteType::Double.is_single());
+    /// ```
+    pub const fn is_single(self) -> bool {
+        matches!(self, Self::Single)
+    }
+
+    /// Returns true if this byte starts a character of two or more bytes
+    ///
+    /// # Example
+    /// ```
+    /// use utf8_parser::Utf8ByteType;
+    ///
+    /// assert!(Utf8ByteType::Double.is_multibyte());
+    /// assert!(Utf8ByteType::Quadruple.is_multibyte());
+    /// assert!(!Utf8ByteType::Single.is_multibyte());
+    /// assert!(!Utf8ByteType::Continuation.is_multibyte());
+    /// ```
+    pub const fn is_multibyte(self) -> bool {
+        matches!(self, Self::Double | Self::Triple | Self::Quadruple)
+    }
+
+    /// Total number of bytes in a character starting with this type of byte
+    ///
+    /// Returns `None` for continuation bytes, which can't start a character.
+    pub const fn sequence_length(self) -> Option<usize> {
+        match self {
+            Self::Continuation => None,
+            Self::Single => Some(1),
+            Self::Double => Some(2),
+            Self::Triple => Some(3),
+            Self::Quadruple => Some(4),
+        }
+    }
+
+    /// Number of continuation bytes that must follow this type of byte
+    pub const fn continuation_count(self) -> usize {
+        match self {
+            Self::Continuation | Self::Single => 0,
+            Self::Double => 1,
+            Self::Triple => 2,
+            Self::Quadruple => 3,
+        }
+    }
+
     const fn id(self) -> u8 {
         match self {
             Self::Single => 0b0,
@@ -80,16 +215,128 @@ impl Utf8ByteType {
         self.id().count_ones() + 1
     }
 
-    const fn value_mask(self) -> u8 {
+    /// The bits of this type of byte that carry part of the code point
+    ///
+    /// The remaining high bits are the fixed prefix that identifies the type, such as `110` for
+    /// [Utf8ByteType::Double].
+    ///
+    /// # Example
+    /// ```
+    /// use utf8_parser::Utf8ByteType;
+    ///
+    /// assert_eq!(Utf8ByteType::Single.value_mask(), 0b0111_1111);
+    /// assert_eq!(Utf8ByteType::Continuation.value_mask(), 0b0011_1111);
+    /// assert_eq!(Utf8ByteType::Double.value_mask(), 0b0001_1111);
+    /// assert_eq!(Utf8ByteType::Triple.value_mask(), 0b0000_1111);
+    /// assert_eq!(Utf8ByteType::Quadruple.value_mask(), 0b0000_0111);
+    /// ```
+    pub const fn value_mask(self) -> u8 {
         0xFF >> self.id_length()
     }
 
-    const fn value_mask_length(self) -> u32 {
+    /// Number of bits of the code point carried by this type of byte
+    ///
+    /// This is the number of ones in [Utf8ByteType::value_mask]: 7 for [Utf8ByteType::Single],
+    /// 6 for [Utf8ByteType::Continuation], and 5, 4, and 3 for the other start bytes.
+    pub const fn payload_bits(self) -> u32 {
         self.value_mask().count_ones()
     }
 
     const fn matches(self, byte: u8) -> bool {
-        (byte >> self.value_mask_length()) == self.id()
+        (byte >> self.payload_bits()) == self.id()
+    }
+}
+
+/// The kind of byte a [Utf8Parser] expects next
+///
+/// # Example
+/// ```
+/// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+/// use utf8_parser::{ExpectedByte, Utf8Parser};
+///
+/// let mut parser = Utf8Parser::new();
+/// assert_eq!(parser.expected(), ExpectedByte::StartByte);
+/// parser.push(0xc3)?;
+/// assert_eq!(parser.expected(), ExpectedByte::Continuation);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum ExpectedByte {
+    /// An ASCII value or a start byte
+    StartByte,
+    /// A continuation byte
+    Continuation,
+}
+
+/// A Unicode code point, which may be a surrogate
+///
+/// Yielded by [Utf8Parser::push_wtf8].
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct CodePoint(u32);
+
+impl CodePoint {
+    /// Get the numeric value of the code point
+    pub const fn to_u32(self) -> u32 {
+        self.0
+    }
+
+    /// Convert to a [char], or `None` if this is a surrogate
+    pub const fn to_char(self) -> Option<char> {
+        char::from_u32(self.0)
+    }
+
+    /// Returns true if this is a surrogate (U+D800 to U+DFFF)
+    pub const fn is_surrogate(self) -> bool {
+        is_surrogate(self.0)
+    }
+}
+
+/// Classification of every byte, indexed by the byte, as returned by [Utf8ByteType::of]
+///
+/// This is computed at compile time, so it can be used as a lookup table in ROM.
+///
+/// # Example
+/// ```
+/// use utf8_parser::{Utf8ByteType, Utf8ParserError, BYTE_TYPE_TABLE};
+///
+/// assert_eq!(BYTE_TYPE_TABLE[0xe2], Ok(Utf8ByteType::Triple));
+/// assert_eq!(BYTE_TYPE_TABLE[0xff], Err(Utf8ParserError::InvalidByte(0xff)));
+/// ```
+pub const BYTE_TYPE_TABLE: [Result<Utf8ByteType, Utf8ParserError>; 256] = {
+    let mut table = [Err(Utf8ParserError::InvalidByte(0)); 256];
+    let mut i = 0;
+    while i < table.len() {
+        table[i] = Utf8ByteType::of(i as u8);
+        i += 1;
+    }
+    table
+};
+
+/// Total length of the sequence that starts with `byte`
+///
+/// Errors with [Utf8ParserError::UnexpectedContinuationByte] for continuation bytes, and
+/// [Utf8ParserError::InvalidByte] for bytes that never appear in UTF-8. This is shorthand for
+/// [Utf8ByteType::of] followed by [Utf8ByteType::sequence_length].
+///
+/// # Example
+/// ```
+/// use utf8_parser::{utf8_len_from_first_byte, Utf8ParserError};
+///
+/// assert_eq!(utf8_len_from_first_byte(b'a'), Ok(1));
+/// assert_eq!(utf8_len_from_first_byte(0xf0), Ok(4));
+/// assert_eq!(
+///     utf8_len_from_first_byte(0x80),
+///     Err(Utf8ParserError::UnexpectedContinuationByte(0x80))
+/// );
+/// ```
+pub const fn utf8_len_from_first_byte(byte: u8) -> Result<usize, Utf8ParserError> {
+    match Utf8ByteType::of(byte) {
+        Ok(kind) => match kind.sequence_length() {
+            Some(len) => Ok(len),
+            None => Err(Utf8ParserError::UnexpectedContinuationByte(byte)),
+        },
+        Err(err) => Err(err),
     }
 }
 
@@ -130,7 +377,8 @@ impl ParsedByte {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum State {
     Fresh,
     OneLeft(u32),
@@ -138,172 +386,2856 @@ enum State {
     ThreeLeft(u32),
 }
 
+// Whether `val` is a control character other than tab, LF or CR
+const fn is_rejected_control(val: u32) -> bool {
+    matches!(val, 0x00..=0x1f | 0x7f..=0x9f) && !matches!(val, 0x09 | 0x0a | 0x0d)
+}
+
+const fn is_surrogate(val: u32) -> bool {
+    val >= FIRST_SURROGATE && val <= LAST_SURROGATE
+}
+
+// Noncharacters are U+FDD0 to U+FDEF, and the last two code points of every plane
+const fn is_noncharacter(val: u32) -> bool {
+    (val >= 0xFDD0 && val <= 0xFDEF) || (val & 0xFFFE) == 0xFFFE
+}
+
+// Optional behaviors of the parser
+//
+// Missing fields deserialize to their defaults, so snapshots from before a mode was added still
+// load.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+struct Config {
+    // Emit U+FFFD instead of erroring
+    lenient: bool,
+    // Allow encoded surrogates
+    wtf8: bool,
+    // Combine encoded surrogate pairs, and disallow four-byte sequences
+    cesu8: bool,
+    // Allow the overlong encoding of NUL, 0xC0 0x80
+    mutf8: bool,
+    // Swallow U+FEFF if it's the first character
+    skip_bom: bool,
+    // Error on noncharacters
+    reject_noncharacters: bool,
+    // Largest code point allowed
+    max_scalar: u32,
+    // Error on control characters other than tab, LF and CR
+    reject_controls: bool,
+    // Yield CRLF as a single LF
+    coalesce_crlf: bool,
+    // Decode overlong encodings instead of erroring
+    allow_overlong: bool,
+    // Longest sequence allowed, in bytes
+    max_sequence_len: u8,
+    // Reject start bytes that can't begin a valid sequence, per RFC 3629
+    strict_start_bytes: bool,
+}
+
+impl Config {
+    const fn new() -> Self {
+        Self {
+            lenient: false,
+            wtf8: false,
+            cesu8: false,
+            mutf8: false,
+            skip_bom: false,
+            reject_noncharacters: false,
+            max_scalar: char::MAX as u32,
+            reject_controls: false,
+            coalesce_crlf: false,
+            allow_overlong: false,
+            max_sequence_len: 4,
+            strict_start_bytes: false,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 const fn push_byte(current: u32, byte: u8) -> u32 {
     debug_assert!(current <= 0x00FFFFFF);
     debug_assert!(byte <= 0b0011_1111);
-    (current << Utf8ByteType::Continuation.value_mask_length()) | (byte as u32)
+    (current << Utf8ByteType::Continuation.payload_bits()) | (byte as u32)
 }
 
-/// A stateful UTF-8 parser.
-///
-/// # Behavior on Errors
-///
-/// [Utf8Parser] will reset on errors. Example:
-///
-/// ```
-/// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
-/// use utf8_parser::Utf8Parser;
-///
-/// let mut parser = Utf8Parser::new();
-/// // Utf-8 start byte
-/// assert!(parser.push(0xf0)?.is_none());
-/// // A continuation byte is expected here, but we're pushing an ASCII char
-/// assert!(parser.push(b'a').is_err());
-/// // The state is reset, so this now no longer errors
-/// assert_eq!(parser.push(b'a'), Ok(Some('a')));
-/// # Ok(())
-/// # }
-/// ```
-#[derive(Clone, Debug)]
-pub struct Utf8Parser {
+impl Config {
+    // Whether every character decoded in this mode is encoded as plain UTF-8 in the input, so
+    // it can be borrowed from it
+    const fn borrows_input(&self) -> bool {
+        !(self.lenient || self.coalesce_crlf || self.skip_bom || self.cesu8 || self.allow_overlong)
+    }
+
+    // Apply optional restrictions to a decoded code point
+    const fn check_code_point(&self, val: u32) -> Result<u32, Utf8ParserError> {
+        if val > self.max_scalar {
+            return Err(Utf8ParserError::CodePointTooLarge(val));
+        }
+        if self.reject_noncharacters && is_noncharacter(val) {
+            return Err(Utf8ParserError::Noncharacter(val));
+        }
+        if self.reject_controls && is_rejected_control(val) {
+            return Err(Utf8ParserError::ControlCharacter(val));
+        }
+        Ok(val)
+    }
+
+    // Whether `byte` is a start byte that's rejected up front by `strict_start_bytes`
+    const fn rejects_start_byte(&self, byte: u8) -> bool {
+        match byte {
+            // MUTF-8 encodes NUL as 0xC0 0x80
+            0xc0 => self.strict_start_bytes && !self.mutf8,
+            // 0xF5 to 0xF7 can only start code points above U+10FFFF
+            0xc1 | 0xf5..=0xf7 => self.strict_start_bytes,
+            _ => false,
+        }
+    }
+
+    // Whether `byte` starts a sequence longer than the configured maximum
+    const fn starts_too_long(&self, byte: u8) -> bool {
+        match Utf8ByteType::of(byte) {
+            Ok(kind) => match kind.sequence_length() {
+                Some(len) => len > self.max_sequence_len as usize,
+                None => false,
+            },
+            Err(_) => false,
+        }
+    }
+}
+
+// The part of a parser's state that decoding a byte reads and updates
+//
+// Everything else, such as the position and pending characters, is bookkeeping around
+// `Core::step`. Keeping this `Copy` and the step `const` means it can also run at compile time.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+pub(crate) struct Core {
     state: State,
+    // In CESU-8 mode, a decoded high surrogate waiting for its low surrogate
+    high_surrogate: Option<u32>,
+    // Total length of the in-progress sequence, including its start byte
+    sequence_len: u8,
 }
 
-impl Utf8Parser {
-    /// Construct a new Utf8Parser
-    pub const fn new() -> Self {
+impl Core {
+    pub(crate) const fn new() -> Self {
         Self {
             state: State::Fresh,
+            high_surrogate: None,
+            sequence_len: 0,
         }
     }
 
-    /// Push a byte into the parser
-    pub fn push(&mut self, byte: u8) -> Result<Option<char>, Utf8ParserError> {
-        match self.push_inner_impl(byte) {
-            Ok(val) => Ok(val),
-            // Reset on error
-            Err(val) => {
-                self.reset();
-                Err(val)
+    // Whether no character is in progress
+    pub(crate) const fn is_idle(&self) -> bool {
+        matches!(self.state, State::Fresh) && self.high_surrogate.is_none()
+    }
+
+    // Length of the character the next byte would complete, including that byte
+    //
+    // Bytes rejected by `try_push` partway through a character count towards the parser's
+    // position, but aren't part of the character, so this counts the sequence instead.
+    pub(crate) const fn char_len(&self) -> u8 {
+        let len = match self.state {
+            State::Fresh => 1,
+            _ => self.sequence_len,
+        };
+        match self.high_surrogate {
+            // A CESU-8 high surrogate is always three bytes
+            Some(_) => len + 3,
+            None => len,
+        }
+    }
+
+    // Decode `byte`, returning the next state and the code point it completes, if any
+    //
+    // This returns a code point rather than a `char`, since WTF-8 mode lets surrogates through.
+    // `push` is hot, and without forced inlining, the returned state goes through memory.
+    #[inline(always)]
+    pub(crate) const fn step(
+        self,
+        config: &Config,
+        byte: u8,
+    ) -> Result<(Self, Option<u32>), Utf8ParserError> {
+        if config.cesu8 && matches!(Utf8ByteType::of(byte), Ok(Utf8ByteType::Quadruple)) {
+            return Err(Utf8ParserError::InvalidByte(byte));
+        }
+        let (mut next, val) = match self.push_sequence(config, byte) {
+            Ok(result) => result,
+            Err(err) => return Err(err),
+        };
+        let val = match val {
+            Some(val) if config.cesu8 => match next.pair_surrogates(val) {
+                Ok((paired, val)) => {
+                    next = paired;
+                    val
+                }
+                Err(err) => return Err(err),
+            },
+            val => val,
+        };
+
+        match val {
+            Some(val) => match config.check_code_point(val) {
+                Ok(val) => Ok((next, Some(val))),
+                Err(err) => Err(err),
+            },
+            None => Ok((next, None)),
+        }
+    }
+
+    // Combine a CESU-8 surrogate pair
+    #[inline(always)]
+    const fn pair_surrogates(self, val: u32) -> Result<(Self, Option<u32>), Utf8ParserError> {
+        let mut next = self;
+        next.high_surrogate = None;
+        match (self.high_surrogate, val) {
+            (None, FIRST_SURROGATE..=LAST_HIGH_SURROGATE) => {
+                next.high_surrogate = Some(val);
+                Ok((next, None))
             }
+            (Some(high), FIRST_LOW_SURROGATE..=LAST_SURROGATE) => Ok((
+                next,
+                Some(
+                    FIRST_CODE_POINT_FOR_QUADRUPLE
+                        + ((high - FIRST_SURROGATE) << 10)
+                        + (val - FIRST_LOW_SURROGATE),
+                ),
+            )),
+            (Some(high), _) => Err(Utf8ParserError::SurrogateCodePoint(high)),
+            (None, FIRST_LOW_SURROGATE..=LAST_SURROGATE) => {
+                Err(Utf8ParserError::SurrogateCodePoint(val))
+            }
+            (None, _) => Ok((next, Some(val))),
         }
     }
 
-    // Inner functionality of `push`
-    fn push_inner_impl(&mut self, byte: u8) -> Result<Option<char>, Utf8ParserError> {
-        let byte = match ParsedByte::from_byte(byte) {
+    // Decode a single UTF-8 sequence
+    #[inline(always)]
+    const fn push_sequence(
+        self,
+        config: &Config,
+        byte: u8,
+    ) -> Result<(Self, Option<u32>), Utf8ParserError> {
+        let parsed = match ParsedByte::from_byte(byte) {
             Ok(v) => v,
             Err(e) => {
                 return Err(e);
             }
         };
+        if matches!(self.state, State::Fresh) {
+            if config.rejects_start_byte(byte) {
+                return Err(Utf8ParserError::InvalidByte(byte));
+            }
+            if config.starts_too_long(byte) {
+                return Err(Utf8ParserError::SequenceTooLong(byte));
+            }
+        }
 
-        match (self.state, byte) {
+        let mut next = self;
+        match (self.state, parsed) {
             (State::OneLeft(current), ParsedByte::ContinuationByte(value)) => {
-                self.state = State::Fresh;
+                next.state = State::Fresh;
                 let val = push_byte(current, value);
-                if val < FIRST_CODE_POINT_FOR_DOUBLE {
+                if val < FIRST_CODE_POINT_FOR_DOUBLE
+                    && !config.allow_overlong
+                    && !(config.mutf8 && val == 0)
+                {
                     return Err(Utf8ParserError::OverlongEncoding);
                 }
-                match char::from_u32(val) {
-                    Some(val) => Ok(Some(val)),
-                    None => Err(Utf8ParserError::InvalidChar(val)),
+                if is_surrogate(val) && !config.wtf8 && !config.cesu8 {
+                    return Err(Utf8ParserError::SurrogateCodePoint(val));
+                }
+                if val > char::MAX as u32 {
+                    return Err(Utf8ParserError::InvalidChar(val));
                 }
+                Ok((next, Some(val)))
             }
             (State::TwoLeft(current), ParsedByte::ContinuationByte(value)) => {
                 let val = push_byte(current, value);
-                if val << Utf8ByteType::Continuation.value_mask_length()
-                    < FIRST_CODE_POINT_FOR_TRIPLE
+                if val << Utf8ByteType::Continuation.payload_bits() < FIRST_CODE_POINT_FOR_TRIPLE
+                    && !config.allow_overlong
                 {
                     return Err(Utf8ParserError::OverlongEncoding);
                 }
-                self.state = State::OneLeft(val);
-                Ok(None)
+                next.state = State::OneLeft(val);
+                Ok((next, None))
             }
             (State::ThreeLeft(current), ParsedByte::ContinuationByte(value)) => {
                 let val = push_byte(current, value);
-                if val << (2 * Utf8ByteType::Continuation.value_mask_length())
+                if val << (2 * Utf8ByteType::Continuation.payload_bits())
                     < FIRST_CODE_POINT_FOR_QUADRUPLE
+                    && !config.allow_overlong
                 {
                     return Err(Utf8ParserError::OverlongEncoding);
                 }
-                self.state = State::TwoLeft(val);
-                Ok(None)
+                next.state = State::TwoLeft(val);
+                Ok((next, None))
             }
-            (State::Fresh, ParsedByte::Single(value)) => Ok(Some(value as char)),
+            (State::Fresh, ParsedByte::Single(value)) => Ok((next, Some(value as u32))),
             (State::Fresh, ParsedByte::StartDouble(value)) => {
-                self.state = State::OneLeft(value as u32);
-                Ok(None)
+                next.state = State::OneLeft(value as u32);
+                next.sequence_len = 2;
+                Ok((next, None))
             }
             (State::Fresh, ParsedByte::StartTriple(value)) => {
-                self.state = State::TwoLeft(value as u32);
-                Ok(None)
+                next.state = State::TwoLeft(value as u32);
+                next.sequence_len = 3;
+                Ok((next, None))
             }
             (State::Fresh, ParsedByte::StartQuadruple(value)) => {
-                self.state = State::ThreeLeft(value as u32);
-                Ok(None)
+                next.state = State::ThreeLeft(value as u32);
+                next.sequence_len = 4;
+                Ok((next, None))
             }
             (
                 State::OneLeft(_) | State::TwoLeft(_) | State::ThreeLeft(_),
-                ParsedByte::Single(value)
-                | ParsedByte::StartDouble(value)
-                | ParsedByte::StartTriple(value)
-                | ParsedByte::StartQuadruple(value),
-            ) => Err(Utf8ParserError::UnexpectedStartByte(value)),
-            (State::Fresh, ParsedByte::ContinuationByte(value)) => {
-                Err(Utf8ParserError::UnexpectedContinuationByte(value))
+                ParsedByte::Single(_)
+                | ParsedByte::StartDouble(_)
+                | ParsedByte::StartTriple(_)
+                | ParsedByte::StartQuadruple(_),
+            ) => Err(Utf8ParserError::UnexpectedStartByte(byte)),
+            (State::Fresh, ParsedByte::ContinuationByte(_)) => {
+                Err(Utf8ParserError::UnexpectedContinuationByte(byte))
             }
         }
     }
+}
 
-    // Reset the state
-    fn reset(&mut self) {
-        self.state = State::Fresh;
-    }
+/// A stateful UTF-8 parser.
+///
+/// # Behavior on Errors
+///
+/// [Utf8Parser] will reset on errors. Example:
+///
+/// ```
+/// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+/// use utf8_parser::Utf8Parser;
+///
+/// let mut parser = Utf8Parser::new();
+/// // Utf-8 start byte
+/// assert!(parser.push(0xf0)?.is_none());
+/// // A continuation byte is expected here, but we're pushing an ASCII char
+/// assert!(parser.push(b'a').is_err());
+/// // The state is reset, so this now no longer errors
+/// assert_eq!(parser.push(b'a'), Ok(Some('a')));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The byte 0x00 is an ordinary one-byte character, U+0000, and input isn't treated as
+/// NUL-terminated. Only [Utf8Parser::new_reject_controls] rejects it.
+///
+/// # Lenient Mode
+///
+/// A parser constructed with [Utf8Parser::new_lenient] never errors. Malformed input is replaced
+/// with U+FFFD REPLACEMENT CHARACTER, following the "substitution of maximal subparts" practice
+/// used by [`String::from_utf8_lossy`].
+///
+/// [`String::from_utf8_lossy`]: https://doc.rust-lang.org/std/string/struct.String.html#method.from_utf8_lossy
+///
+/// A single byte can complete two replacement characters, so in this mode the second one is held
+/// by the parser until it's retrieved with [Utf8Parser::pop_pending]. Combined with
+/// [CRLF Mode](Utf8Parser#crlf-mode), a byte can yield three characters, so keep calling
+/// [Utf8Parser::pop_pending] until it returns `None`.
+///
+/// ```
+/// use utf8_parser::Utf8Parser;
+///
+/// let mut parser = Utf8Parser::new_lenient();
+/// assert_eq!(parser.push(0xe0), Ok(None));
+/// // 0xe0 can't be followed by 0x80, so both bytes are replaced
+/// assert_eq!(parser.push(0x80), Ok(Some('\u{FFFD}')));
+/// assert_eq!(parser.pop_pending(), Some('\u{FFFD}'));
+/// assert_eq!(parser.pop_pending(), None);
+/// ```
+///
+/// # WTF-8 Mode
+///
+/// [WTF-8](https://simonsapin.github.io/wtf-8/) is a superset of UTF-8 that can also encode lone
+/// surrogates, and is used to represent potentially ill-formed UTF-16, such as Windows file
+/// names. **This deviates from strict UTF-8, and should only be used by WTF-8 consumers.**
+///
+/// A parser constructed with [Utf8Parser::new_wtf8] yields encoded surrogates through
+/// [Utf8Parser::push_wtf8]. Since surrogates can't be represented as a [char],
+/// [Utf8Parser::push] and [Utf8Parser::push_scalar] still reject them. Combined with
+/// [Lenient Mode](Utf8Parser#lenient-mode), each encoded surrogate is replaced with a single
+/// U+FFFD. It can't be combined with [CRLF Mode](Utf8Parser#crlf-mode).
+///
+/// ```
+/// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+/// use utf8_parser::Utf8Parser;
+///
+/// let mut parser = Utf8Parser::new_wtf8();
+/// parser.push_wtf8(0xed)?;
+/// parser.push_wtf8(0xa0)?;
+/// let code_point = parser.push_wtf8(0x80)?.unwrap();
+/// assert_eq!(code_point.to_u32(), 0xd800);
+/// assert_eq!(code_point.to_char(), None);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # CESU-8 Mode
+///
+/// [CESU-8](https://www.unicode.org/reports/tr26/) encodes supplementary characters as a pair of
+/// three-byte surrogate sequences, rather than one four-byte sequence. It's used by some Java and
+/// Oracle systems.
+///
+/// A parser constructed with [Utf8Parser::new_cesu8] combines surrogate pairs into a single
+/// [char], and rejects four-byte sequences. An unpaired surrogate is an error, and a high
+/// surrogate still waiting for its pair is treated as an incomplete character by
+/// [Utf8Parser::finish].
+///
+/// ```
+/// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+/// use utf8_parser::Utf8Parser;
+///
+/// let mut parser = Utf8Parser::new_cesu8();
+/// // U+D83C
+/// for byte in [0xed, 0xa0, 0xbc] {
+///     assert_eq!(parser.push(byte)?, None);
+/// }
+/// // U+DF84
+/// assert_eq!(parser.push(0xed)?, None);
+/// assert_eq!(parser.push(0xbe)?, None);
+/// assert_eq!(parser.push(0x84)?, Some('🎄'));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # CRLF Mode
+///
+/// A parser constructed with [Utf8Parser::new_coalesce_crlf] yields `\r\n` as a single `\n`.
+/// A `\r` is held back until the next character is decoded. If that character isn't `\n`, the
+/// parser yields the `\r` and holds the other character until it's retrieved with
+/// [Utf8Parser::pop_pending]. [Utf8Parser::finish] flushes a trailing `\r` the same way, as
+/// does an error, so a `\r` and `\n` on either side of invalid bytes aren't coalesced.
+///
+/// ```
+/// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+/// use utf8_parser::Utf8Parser;
+///
+/// let mut parser = Utf8Parser::new_coalesce_crlf();
+/// assert_eq!(parser.push(b'\r')?, None);
+/// assert_eq!(parser.push(b'\n')?, Some('\n'));
+/// assert_eq!(parser.push(b'\r')?, None);
+/// assert_eq!(parser.push(b'a')?, Some('\r'));
+/// assert_eq!(parser.pop_pending(), Some('a'));
+/// assert_eq!(parser.push(b'\r')?, None);
+/// parser.finish()?;
+/// assert_eq!(parser.pop_pending(), Some('\r'));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Overlong Mode
+///
+/// **This mode is dangerous, and should only be used to round-trip legacy data that's known to
+/// contain overlong encodings.** Overlong encodings let the same character be spelled several
+/// ways, so input that's been checked for a character, such as `/` or `\0`, can still decode
+/// to it. Never decode untrusted input in this mode before validating it.
+///
+/// A parser constructed with [Utf8Parser::new_allow_overlong] decodes overlong encodings
+/// as the code point they encode, instead of erroring with
+/// [Utf8ParserError::OverlongEncoding]. Surrogates and code points above U+10FFFF are still
+/// rejected.
+///
+/// ```
+/// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+/// use utf8_parser::Utf8Parser;
+///
+/// let mut parser = Utf8Parser::new_allow_overlong();
+/// // '/', as a two-byte sequence
+/// assert_eq!(parser.push(0xc0)?, None);
+/// assert_eq!(parser.push(0xaf)?, Some('/'));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Utf8Parser {
+    core: Core,
+    config: Config,
+    // Output held back for `pop_pending`
+    pending: PendingChars,
+    // Number of bytes pushed over the parser's lifetime
+    bytes_consumed: u64,
+    // Whether any character has been decoded yet
+    seen_first_char: bool,
+    // In CRLF mode, where a carriage return being held back came from
+    held_cr: Option<Span>,
+    // Offset passed to `push_at` with the first byte of the in-progress sequence
+    sequence_start: u64,
+    // Position of the first byte of the in-progress sequence
+    char_start: u64,
+    #[cfg(feature = "stats")]
+    stats: Utf8Stats,
+    #[cfg(feature = "debug-history")]
+    history: history::ByteHistory,
 }
 
-impl Default for Utf8Parser {
-    fn default() -> Self {
-        Self::new()
+impl Utf8Parser {
+    /// Construct a new Utf8Parser
+    pub const fn new() -> Self {
+        Self::with_config(Config::new())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use rand::Rng;
+    /// Construct a new Utf8Parser that replaces malformed input with U+FFFD instead of erroring
+    ///
+    /// See [Lenient Mode](Utf8Parser#lenient-mode).
+    pub const fn new_lenient() -> Self {
+        let mut config = Config::new();
+        config.lenient = true;
+        Self::with_config(config)
+    }
 
-    #[test]
-    fn conversion() -> Result<(), Utf8ParserError> {
-        let test_vectors = &[
-            (0x00, ParsedByte::Single(0x00)),
-            (0x01, ParsedByte::Single(0x01)),
-            (0x65, ParsedByte::Single(0x65)),
-            (0x7f, ParsedByte::Single(0x7f)),
-            (0b110_00000, ParsedByte::StartDouble(0)),
-            (0b110_00001, ParsedByte::StartDouble(0b1)),
-            (0b110_11001, ParsedByte::StartDouble(0b11001)),
-            (0b110_11111, ParsedByte::StartDouble(0b11111)),
-            (0b1110_0000, ParsedByte::StartTriple(0)),
-            (0b1110_0001, ParsedByte::StartTriple(0b1)),
-            (0b1110_1001, ParsedByte::StartTriple(0b1001)),
-            (0b1110_1111, ParsedByte::StartTriple(0b1111)),
-            (0b1111_0000, ParsedByte::StartQuadruple(0)),
-            (0b1111_0001, ParsedByte::StartQuadruple(0b1)),
-            (0b1111_0111, ParsedByte::StartQuadruple(0b111)),
-            (0x80, ParsedByte::ContinuationByte(0x00)),
-            (0x81, ParsedByte::ContinuationByte(0x01)),
-            (0b10_111111, ParsedByte::ContinuationByte(0b111111)),
-        ];
+    /// Construct a new Utf8Parser that decodes WTF-8 rather than UTF-8
+    ///
+    /// See [WTF-8 Mode](Utf8Parser#wtf-8-mode).
+    pub const fn new_wtf8() -> Self {
+        let mut config = Config::new();
+        config.wtf8 = true;
+        Self::with_config(config)
+    }
 
-        for tv in test_vectors.iter() {
-            assert_eq!(ParsedByte::from_byte(tv.0)?, tv.1);
-        }
+    /// Construct a new Utf8Parser that decodes CESU-8 rather than UTF-8
+    ///
+    /// See [CESU-8 Mode](Utf8Parser#cesu-8-mode).
+    pub const fn new_cesu8() -> Self {
+        let mut config = Config::new();
+        config.cesu8 = true;
+        Self::with_config(config)
+    }
 
-        Ok(())
+    /// Construct a new Utf8Parser that decodes Java's Modified UTF-8
+    ///
+    /// This is [CESU-8 Mode](Utf8Parser#cesu-8-mode), except that U+0000 may also be encoded as
+    /// the two bytes `0xC0 0x80`. Other overlong encodings are still rejected, and a raw 0x00
+    /// byte is still accepted.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::Utf8Parser;
+    ///
+    /// let mut parser = Utf8Parser::new_mutf8();
+    /// assert_eq!(parser.push(0xc0)?, None);
+    /// assert_eq!(parser.push(0x80)?, Some('\0'));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub const fn new_mutf8() -> Self {
+        let mut config = Config::new();
+        config.cesu8 = true;
+        config.mutf8 = true;
+        Self::with_config(config)
     }
 
-    #[test]
+    /// Construct a new Utf8Parser that skips a leading byte order mark
+    ///
+    /// If the first character decoded is U+FEFF, it's dropped rather than returned. U+FEFF
+    /// anywhere else in the stream is unaffected.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::Utf8Parser;
+    ///
+    /// let mut parser = Utf8Parser::new_skip_bom();
+    /// let bytes = "\u{feff}a\u{feff}".as_bytes();
+    /// let decoded: String = parser.feed(bytes).collect::<Result<_, _>>()?;
+    /// assert_eq!(decoded, "a\u{feff}");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub const fn new_skip_bom() -> Self {
+        let mut config = Config::new();
+        config.skip_bom = true;
+        Self::with_config(config)
+    }
+
+    /// Construct a new Utf8Parser that rejects noncharacters
+    ///
+    /// Noncharacters are U+FDD0 to U+FDEF, plus the last two code points of every plane (U+FFFE,
+    /// U+FFFF, U+1FFFE, U+1FFFF, and so on). They're valid Unicode scalar values, but some
+    /// protocols forbid them. Decoding one errors with [Utf8ParserError::Noncharacter].
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::{Utf8Parser, Utf8ParserError};
+    ///
+    /// let mut parser = Utf8Parser::new_reject_noncharacters();
+    /// parser.push(0xef)?;
+    /// parser.push(0xbf)?;
+    /// assert_eq!(parser.push(0xbf), Err(Utf8ParserError::Noncharacter(0xffff)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub const fn new_reject_noncharacters() -> Self {
+        let mut config = Config::new();
+        config.reject_noncharacters = true;
+        Self::with_config(config)
+    }
+
+    /// Construct a new Utf8Parser that rejects control characters
+    ///
+    /// The C0 controls (U+0000 to U+001F), DEL (U+007F) and the C1 controls (U+0080 to U+009F)
+    /// error with [Utf8ParserError::ControlCharacter], except for tab, line feed, and carriage
+    /// return, which are allowed.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::{Utf8Parser, Utf8ParserError};
+    ///
+    /// let mut parser = Utf8Parser::new_reject_controls();
+    /// assert_eq!(parser.push(b'\n')?, Some('\n'));
+    /// assert_eq!(parser.push(0x1b), Err(Utf8ParserError::ControlCharacter(0x1b)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub const fn new_reject_controls() -> Self {
+        let mut config = Config::new();
+        config.reject_controls = true;
+        Self::with_config(config)
+    }
+
+    /// Construct a new Utf8Parser that only accepts characters in the Basic Multilingual Plane
+    ///
+    /// Characters above U+FFFF error with [Utf8ParserError::CodePointTooLarge]. By default, every
+    /// code point up to U+10FFFF is accepted.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::{Utf8Parser, Utf8ParserError};
+    ///
+    /// let mut parser = Utf8Parser::new_bmp_only();
+    /// for byte in [0xf0, 0x9f, 0x8e] {
+    ///     parser.push(byte)?;
+    /// }
+    /// assert_eq!(parser.push(0x84), Err(Utf8ParserError::CodePointTooLarge(0x1f384)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub const fn new_bmp_only() -> Self {
+        let mut config = Config::new();
+        config.max_scalar = FIRST_CODE_POINT_FOR_QUADRUPLE - 1;
+        Self::with_config(config)
+    }
+
+    /// Construct a new Utf8Parser in [CRLF Mode](Utf8Parser#crlf-mode)
+    pub const fn new_coalesce_crlf() -> Self {
+        let mut config = Config::new();
+        config.coalesce_crlf = true;
+        Self::with_config(config)
+    }
+
+    /// Construct a new Utf8Parser in [Overlong Mode](Utf8Parser#overlong-mode)
+    ///
+    /// **Overlong encodings are a security risk. Read the linked section before using this.**
+    pub const fn new_allow_overlong() -> Self {
+        let mut config = Config::new();
+        config.allow_overlong = true;
+        Self::with_config(config)
+    }
+
+    /// Construct a Utf8Parser that resumes from a state saved by [Utf8Parser::save_state]
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::Utf8Parser;
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// parser.push(0xc3)?;
+    /// let state = parser.save_state();
+    ///
+    /// let mut parser = Utf8Parser::from_state(state);
+    /// assert_eq!(parser.push(0xa9)?, Some('é'));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub const fn from_state(state: Utf8ParserState) -> Self {
+        let mut parser = Self::with_config(state.config);
+        parser.core = state.core;
+        parser.pending = state.pending;
+        parser.bytes_consumed = state.bytes_consumed;
+        parser.seen_first_char = state.seen_first_char;
+        parser.held_cr = state.held_cr;
+        parser.sequence_start = state.sequence_start;
+        parser.char_start = state.char_start;
+        parser
+    }
+
+    /// Take a snapshot of the parser, including any partially-decoded character
+    ///
+    /// See [Utf8ParserState].
+    pub const fn save_state(&self) -> Utf8ParserState {
+        Utf8ParserState {
+            core: self.core,
+            config: self.config,
+            pending: self.pending,
+            bytes_consumed: self.bytes_consumed,
+            seen_first_char: self.seen_first_char,
+            held_cr: self.held_cr,
+            sequence_start: self.sequence_start,
+            // Only meaningful partway through a character
+            char_start: if self.is_idle() { 0 } else { self.char_start },
+        }
+    }
+
+    /// Construct a [Utf8ParserBuilder], to configure a parser with any combination of modes
+    pub const fn builder() -> Utf8ParserBuilder {
+        Utf8ParserBuilder::new()
+    }
+
+    const fn with_config(config: Config) -> Self {
+        Self {
+            core: Core::new(),
+            config,
+            pending: PendingChars::new(),
+            bytes_consumed: 0,
+            seen_first_char: false,
+            held_cr: None,
+            sequence_start: 0,
+            char_start: 0,
+            #[cfg(feature = "stats")]
+            stats: Utf8Stats::new(),
+            #[cfg(feature = "debug-history")]
+            history: history::ByteHistory::new(),
+        }
+    }
+
+    /// Push a byte into the parser
+    pub fn push(&mut self, byte: u8) -> Result<Option<char>, Utf8ParserError> {
+        // `push_scalar` only ever yields valid scalar values
+        Ok(self.push_scalar(byte)?.and_then(char::from_u32))
+    }
+
+    /// Push a byte into the parser, leaving its state untouched on error
+    ///
+    /// [Utf8Parser::push] resets the parser when it errors, so the next byte starts a new
+    /// character. This doesn't, so the partial character can still be inspected with
+    /// [Utf8Parser::partial_bytes] or [Utf8Parser::remaining]. Call [Utf8Parser::reset] to
+    /// carry on with a fresh character, or push another byte to try to continue the sequence.
+    ///
+    /// The rejected byte still counts towards [Utf8Parser::position].
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::{Utf8Parser, Utf8ParserError};
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// parser.try_push(0xf0)?;
+    /// parser.try_push(0x9f)?;
+    /// assert_eq!(parser.try_push(b'a'), Err(Utf8ParserError::UnexpectedStartByte(b'a')));
+    ///
+    /// let mut buf = [0; 4];
+    /// assert_eq!(parser.partial_bytes(&mut buf), [0xf0, 0x9f]);
+    /// assert_eq!(parser.remaining(), 2);
+    ///
+    /// parser.reset();
+    /// assert_eq!(parser.try_push(b'a')?, Some('a'));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn try_push(&mut self, byte: u8) -> Result<Option<char>, Utf8ParserError> {
+        let saved = self.save_state();
+        let result = self.push(byte);
+        if result.is_err() {
+            self.restore(saved);
+        }
+        result
+    }
+
+    /// Push a byte into the parser, reporting errors with a caller-provided offset
+    ///
+    /// `offset` is the byte's position in whatever coordinates the caller uses, such as a file
+    /// offset. On error, this returns the offset given for the first byte of the sequence that
+    /// failed, unlike [Utf8Parser::position], which only counts bytes pushed into this parser.
+    ///
+    /// # Example
+    /// ```
+    /// use utf8_parser::{Utf8Parser, Utf8ParserError};
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// // A truncated '🎄' at offset 0x1000 of a file
+    /// let bytes = [0xf0, 0x9f, 0x8e, b'a'];
+    /// let results: Vec<_> = (0x1000..)
+    ///     .zip(bytes)
+    ///     .map(|(offset, byte)| parser.push_at(byte, offset))
+    ///     .collect();
+    /// assert_eq!(results[3], Err((Utf8ParserError::UnexpectedStartByte(b'a'), 0x1000)));
+    /// ```
+    pub fn push_at(
+        &mut self,
+        byte: u8,
+        offset: u64,
+    ) -> Result<Option<char>, (Utf8ParserError, u64)> {
+        if self.is_idle() {
+            self.sequence_start = offset;
+        }
+        self.push(byte).map_err(|err| (err, self.sequence_start))
+    }
+
+    // Roll back to a saved state, keeping the position, stats and history
+    fn restore(&mut self, saved: Utf8ParserState) {
+        self.core = saved.core;
+        self.pending = saved.pending;
+        self.seen_first_char = saved.seen_first_char;
+        self.held_cr = saved.held_cr;
+        self.sequence_start = saved.sequence_start;
+        self.char_start = saved.char_start;
+    }
+
+    /// Push a byte into the parser, yielding the character along with the number of bytes that
+    /// encoded it
+    ///
+    /// The count is the number of bytes the returned character was decoded from, which aren't
+    /// necessarily the latest ones: in [CRLF Mode](Utf8Parser#crlf-mode), a held-back `\r` is
+    /// returned by the byte after it. The count is 1 to 4, or 6 for a surrogate pair in
+    /// [CESU-8 Mode](Utf8Parser#cesu-8-mode). A U+FFFD in [Lenient Mode](Utf8Parser#lenient-mode)
+    /// counts the bytes it replaced, and a `\n` that replaced `\r\n` counts both bytes.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::Utf8Parser;
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// assert_eq!(parser.push_counted(b'a')?, Some(('a', 1)));
+    /// assert_eq!(parser.push_counted(0xc3)?, None);
+    /// assert_eq!(parser.push_counted(0xa9)?, Some(('é', 2)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn push_counted(&mut self, byte: u8) -> Result<Option<(char, usize)>, Utf8ParserError> {
+        Ok(self
+            .push_char_spanned(byte)?
+            .map(|(c, span)| (c, span.len as usize)))
+    }
+
+    // `push`, also yielding the bytes the character came from
+    fn push_char_spanned(&mut self, byte: u8) -> Result<Option<SpannedChar>, Utf8ParserError> {
+        match self.push_spanned(byte)? {
+            Some((code_point, span)) => match code_point.to_char() {
+                Some(c) => Ok(Some((c, span))),
+                // Only reachable in WTF-8 mode
+                None => Err(Utf8ParserError::SurrogateCodePoint(code_point.to_u32())),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Push a byte into the parser, panicking unless it completes a character
+    ///
+    /// This is shorthand for tests that would otherwise compare against `Ok(Some(c))`. The
+    /// panic message includes the byte and what [Utf8Parser::push] returned.
+    ///
+    /// # Example
+    /// ```
+    /// use utf8_parser::Utf8Parser;
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// assert_eq!(parser.expect_char(b'a'), 'a');
+    /// assert_eq!(parser.push(0xc3), Ok(None));
+    /// assert_eq!(parser.expect_char(0xa9), 'é');
+    /// ```
+    #[cfg(any(test, feature = "testing"))]
+    #[track_caller]
+    pub fn expect_char(&mut self, byte: u8) -> char {
+        match self.push(byte) {
+            Ok(Some(c)) => c,
+            result => panic!("expected {byte:#04x} to complete a character, got {result:?}"),
+        }
+    }
+
+    /// Push a byte into the parser, writing a completed character to `out` as UTF-16
+    ///
+    /// Returns the populated part of `out`: one code unit, or a surrogate pair for characters
+    /// above U+FFFF. As with [Utf8Parser::push], a character held back by the parser is
+    /// retrieved with [Utf8Parser::pop_pending].
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::Utf8Parser;
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// let mut out = [0; 2];
+    /// assert_eq!(parser.push_utf16(b'a', &mut out)?, Some(&[0x61][..]));
+    /// for byte in [0xf0, 0x9f, 0x8e] {
+    ///     assert_eq!(parser.push_utf16(byte, &mut out)?, None);
+    /// }
+    /// assert_eq!(parser.push_utf16(0x84, &mut out)?, Some(&[0xd83c, 0xdf84][..]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn push_utf16<'a>(
+        &mut self,
+        byte: u8,
+        out: &'a mut [u16; 2],
+    ) -> Result<Option<&'a [u16]>, Utf8ParserError> {
+        Ok(self.push(byte)?.map(|c| &*c.encode_utf16(out)))
+    }
+
+    /// Push a byte into the parser, yielding the code point as a `u32` rather than a [char]
+    ///
+    /// This performs the same validation as [Utf8Parser::push], so any returned value is a valid
+    /// Unicode scalar value.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::Utf8Parser;
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// assert_eq!(parser.push_scalar(0xc3)?, None);
+    /// assert_eq!(parser.push_scalar(0xa9)?, Some(0xe9));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn push_scalar(&mut self, byte: u8) -> Result<Option<u32>, Utf8ParserError> {
+        match self.push_wtf8(byte)? {
+            // Only reachable in WTF-8 mode
+            Some(code_point) if code_point.is_surrogate() => {
+                Err(Utf8ParserError::SurrogateCodePoint(code_point.to_u32()))
+            }
+            code_point => Ok(code_point.map(CodePoint::to_u32)),
+        }
+    }
+
+    /// Push a byte into the parser, yielding a [CodePoint]
+    ///
+    /// In [WTF-8 Mode](Utf8Parser#wtf-8-mode), this yields encoded surrogates rather than
+    /// erroring. Otherwise, it behaves like [Utf8Parser::push_scalar].
+    pub fn push_wtf8(&mut self, byte: u8) -> Result<Option<CodePoint>, Utf8ParserError> {
+        Ok(self.push_spanned(byte)?.map(|(code_point, _)| code_point))
+    }
+
+    // `push_wtf8`, also yielding the bytes the code point came from
+    fn push_spanned(&mut self, byte: u8) -> Result<Option<(CodePoint, Span)>, Utf8ParserError> {
+        if !self.pending.has_room(MAX_QUEUED) {
+            return Err(Utf8ParserError::OutputBufferFull);
+        }
+        if self.is_idle() {
+            self.char_start = self.bytes_consumed;
+        }
+        self.bytes_consumed += 1;
+        #[cfg(feature = "debug-history")]
+        self.history.record(&[byte]);
+
+        let (decoded, next) = if self.config.lenient {
+            let (c, next) = self.push_lenient(byte);
+            (c.map(|(c, span)| (CodePoint(c as u32), span)), next)
+        } else {
+            let span = Span {
+                start: self.char_start,
+                len: self.core.char_len(),
+            };
+            match self.push_inner_impl(byte) {
+                Ok(val) => (val.map(|val| (CodePoint(val), span)), None),
+                // Reset on error
+                Err(val) => {
+                    self.reset();
+                    if let Some(cr) = self.held_cr.take() {
+                        self.pending.push('\r', cr);
+                    }
+                    #[cfg(feature = "stats")]
+                    self.stats.record_error();
+                    return Err(val);
+                }
+            }
+        };
+
+        if next.is_none() && !self.config.coalesce_crlf {
+            return Ok(decoded.filter(|(code_point, _)| !self.is_leading_bom(*code_point)));
+        }
+        let mut out = None;
+        let next = next.map(|(c, span)| (CodePoint(c as u32), span));
+        for (code_point, span) in [decoded, next].into_iter().flatten() {
+            self.emit(code_point, span, &mut out);
+        }
+        Ok(out)
+    }
+
+    // Apply the BOM and CRLF modes to a decoded code point, putting the first resulting code
+    // point in `out`, and queueing the rest for `pop_pending`
+    fn emit(&mut self, code_point: CodePoint, span: Span, out: &mut Option<(CodePoint, Span)>) {
+        if self.is_leading_bom(code_point) {
+            return;
+        }
+        if !self.config.coalesce_crlf {
+            self.output(code_point, span, out);
+            return;
+        }
+
+        // Hold back a carriage return until we know whether a line feed follows it. A LF
+        // replaces the held CR, and covers the bytes of both, but not bytes `try_push` rejected
+        // between them. Anything else has to wait until the held CR is yielded.
+        let mut span = span;
+        let is_cr = code_point.to_u32() == '\r' as u32;
+        match self.held_cr.take() {
+            Some(cr) if code_point.to_u32() == '\n' as u32 => {
+                span = Span {
+                    start: cr.start,
+                    len: cr.len + span.len,
+                };
+            }
+            Some(cr) => self.output(CodePoint('\r' as u32), cr, out),
+            None => {}
+        }
+        if is_cr {
+            self.held_cr = Some(span);
+        } else {
+            self.output(code_point, span, out);
+        }
+    }
+
+    fn output(&mut self, code_point: CodePoint, span: Span, out: &mut Option<(CodePoint, Span)>) {
+        if out.is_none() {
+            *out = Some((code_point, span));
+        } else if let Some(c) = code_point.to_char() {
+            self.pending.push(c, span);
+        }
+    }
+
+    // Whether `code_point` is a BOM that should be skipped. Must be called on every decoded code
+    // point.
+    fn is_leading_bom(&mut self, code_point: CodePoint) -> bool {
+        let first = !self.seen_first_char;
+        self.seen_first_char = true;
+        self.config.skip_bom && first && code_point.to_u32() == BYTE_ORDER_MARK
+    }
+
+    /// Take the next character held back by [Utf8Parser::push], [Utf8Parser::push_chunk] or
+    /// [Utf8Parser::finish], if any
+    ///
+    /// Held characters come out in the order they were decoded. Call this until it returns
+    /// `None` after each push: a single byte can produce up to three characters in
+    /// [Lenient Mode](Utf8Parser#lenient-mode) combined with [CRLF Mode](Utf8Parser#crlf-mode).
+    /// If they aren't, and there might not be room for what the next byte produces, pushing
+    /// errors with [Utf8ParserError::OutputBufferFull] without consuming the byte.
+    ///
+    /// Other than after [Utf8Parser::push_chunk], this only ever returns a value in those two
+    /// modes.
+    pub fn pop_pending(&mut self) -> Option<char> {
+        self.pending.pop()
+    }
+
+    // Take every held character at once
+    fn take_pending(&mut self) -> PendingChars {
+        core::mem::replace(&mut self.pending, PendingChars::new())
+    }
+
+    /// Signal the end of input
+    ///
+    /// Errors with [Utf8ParserError::UnexpectedEndOfInput] if the parser is partway through a
+    /// multi-byte character. The parser is reset either way.
+    ///
+    /// In [Lenient Mode](Utf8Parser#lenient-mode), the truncated sequence is instead replaced
+    /// with U+FFFD, which can be retrieved with [Utf8Parser::pop_pending]. This errors with
+    /// [Utf8ParserError::OutputBufferFull], and leaves the parser as it was, if the characters
+    /// already pending leave no room.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::{Utf8Parser, Utf8ParserError};
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// parser.push(b'a')?;
+    /// assert!(parser.finish().is_ok());
+    /// parser.push(0xc3)?;
+    /// assert_eq!(parser.finish(), Err(Utf8ParserError::UnexpectedEndOfInput));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn finish(&mut self) -> Result<(), Utf8ParserError> {
+        let queued =
+            self.held_cr.is_some() as usize + (self.in_progress() && self.config.lenient) as usize;
+        if !self.pending.has_room(queued) {
+            return Err(Utf8ParserError::OutputBufferFull);
+        }
+        if let Some(cr) = self.held_cr.take() {
+            self.pending.push('\r', cr);
+        }
+        if self.in_progress() {
+            self.reset();
+            #[cfg(feature = "stats")]
+            self.stats.record_error();
+            if self.config.lenient {
+                let span = Span::between(self.char_start, self.bytes_consumed);
+                self.pending.push(char::REPLACEMENT_CHARACTER, span);
+                return Ok(());
+            }
+            return Err(Utf8ParserError::UnexpectedEndOfInput);
+        }
+        Ok(())
+    }
+
+    /// Push a slice of bytes into the parser, yielding each completed character
+    ///
+    /// Parser state carries over between calls, so a multi-byte sequence may be split across
+    /// slices. Errors reset the parser just like [Utf8Parser::push], and iteration continues with
+    /// the next byte.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::Utf8Parser;
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// let bytes = "h🎄".as_bytes();
+    /// let chars: Vec<_> = parser.feed(&bytes[..3]).collect::<Result<_, _>>()?;
+    /// assert_eq!(chars, ['h']);
+    /// let chars: Vec<_> = parser.feed(&bytes[3..]).collect::<Result<_, _>>()?;
+    /// assert_eq!(chars, ['🎄']);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn feed<'a>(
+        &'a mut self,
+        bytes: &'a [u8],
+    ) -> impl Iterator<Item = Result<char, Utf8ParserError>> + 'a {
+        let mut bytes = bytes;
+        let mut error = None;
+        core::iter::from_fn(move || loop {
+            if let Some(c) = self.pop_pending() {
+                return Some(Ok(c));
+            }
+            // A CR flushed by an error came before it
+            if let Some(err) = error.take() {
+                return Some(Err(err));
+            }
+
+            // The same ASCII fast path as `push_slice`, a byte at a time so nothing is decoded
+            // ahead of what's been taken
+            let mut ascii = ['\0'];
+            if self.push_ascii(bytes, &mut ascii) == 1 {
+                bytes = &bytes[1..];
+                return Some(Ok(ascii[0]));
+            }
+
+            let (byte, rest) = bytes.split_first()?;
+            bytes = rest;
+            match self.push(*byte) {
+                Ok(Some(c)) => return Some(Ok(c)),
+                Ok(None) => {}
+                Err(err) => error = Some(err),
+            }
+        })
+    }
+
+    /// Like [Utf8Parser::feed], but also yields the position of each character's first byte
+    ///
+    /// Positions count from the first byte pushed over the parser's lifetime, as with
+    /// [Utf8Parser::position], so they stay correct when a character is split across calls.
+    /// A U+FFFD in [Lenient Mode](Utf8Parser#lenient-mode) has the position of the first byte it
+    /// replaced, and a `\n` that replaced `\r\n` in [CRLF Mode](Utf8Parser#crlf-mode) has the
+    /// position of the `\r`.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::Utf8Parser;
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// let bytes = "h🎄!".as_bytes();
+    /// let chars: Vec<_> = parser.feed_positioned(&bytes[..3]).collect::<Result<_, _>>()?;
+    /// assert_eq!(chars, [(0, 'h')]);
+    /// let chars: Vec<_> = parser.feed_positioned(&bytes[3..]).collect::<Result<_, _>>()?;
+    /// assert_eq!(chars, [(1, '🎄'), (5, '!')]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn feed_positioned<'a>(
+        &'a mut self,
+        bytes: &'a [u8],
+    ) -> impl Iterator<Item = Result<(u64, char), Utf8ParserError>> + 'a {
+        bytes.iter().flat_map(move |byte| {
+            let result = self.push_char_spanned(*byte).transpose();
+            let mut pending = self.take_pending();
+            let pending = core::iter::from_fn(move || pending.pop_spanned());
+            // A CR flushed by an error came before it
+            let (before, after) = match result {
+                Some(Err(_)) => (None, result),
+                _ => (result, None),
+            };
+            before
+                .into_iter()
+                .chain(pending.map(Ok))
+                .chain(after)
+                .map(|result| result.map(|(c, span)| (span.start, c)))
+        })
+    }
+
+    /// Push bytes into the parser until they run out or `out` is full
+    ///
+    /// Returns the number of bytes consumed and the number of characters written to `out`. A
+    /// trailing partial sequence is kept in the parser and counted as consumed.
+    ///
+    /// On error, this returns the same counts along with it, with the byte that errored counted
+    /// as consumed, so decoding can carry on with the rest of `bytes`.
+    ///
+    /// # Example
+    /// ```
+    /// use utf8_parser::{Utf8Parser, Utf8ParserError};
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// let mut out = ['\0'; 2];
+    /// let bytes = "abé🎄".as_bytes();
+    /// assert_eq!(parser.push_slice(bytes, &mut out), Ok((2, 2)));
+    /// assert_eq!(out, ['a', 'b']);
+    /// assert_eq!(parser.push_slice(&bytes[2..6], &mut out), Ok((4, 1)));
+    /// assert_eq!(out[0], 'é');
+    /// assert_eq!(parser.push_slice(&bytes[6..], &mut out), Ok((2, 1)));
+    /// assert_eq!(out[0], '🎄');
+    ///
+    /// assert_eq!(
+    ///     parser.push_slice(&[b'c', 0xff, b'd'], &mut out),
+    ///     Err((Utf8ParserError::InvalidByte(0xff), 2, 1))
+    /// );
+    /// assert_eq!(out[0], 'c');
+    /// ```
+    pub fn push_slice(
+        &mut self,
+        bytes: &[u8],
+        out: &mut [char],
+    ) -> Result<(usize, usize), (Utf8ParserError, usize, usize)> {
+        let mut consumed = 0;
+        let mut written = 0;
+
+        while written < out.len() {
+            if let Some(c) = self.pop_pending() {
+                out[written] = c;
+                written += 1;
+                continue;
+            }
+
+            let run = self.push_ascii(&bytes[consumed..], &mut out[written..]);
+            if run > 0 {
+                consumed += run;
+                written += run;
+                continue;
+            }
+
+            let Some(byte) = bytes.get(consumed) else {
+                break;
+            };
+            consumed += 1;
+            match self.push(*byte) {
+                Ok(Some(c)) => {
+                    out[written] = c;
+                    written += 1;
+                }
+                Ok(None) => {}
+                Err(err) => return Err((err, consumed, written)),
+            }
+        }
+
+        Ok((consumed, written))
+    }
+
+    /// Push a chunk of bytes into the parser, borrowing every complete character from `bytes`
+    ///
+    /// A partial sequence at the end of `bytes` is kept in the parser, and completed by the next
+    /// call. A character completed this way can't be borrowed, so it's held until it's retrieved
+    /// with [Utf8Parser::pop_pending], and comes before the returned slice.
+    ///
+    /// On error, this returns the complete characters before the error along with it, and the
+    /// bytes after the error are discarded.
+    ///
+    /// Modes that only restrict which characters are accepted, such as
+    /// [Utf8Parser::new_reject_controls], are supported, though they're slower.
+    ///
+    /// # Panics
+    /// Panics in [Lenient Mode](Utf8Parser#lenient-mode), [CRLF Mode](Utf8Parser#crlf-mode),
+    /// [CESU-8 Mode](Utf8Parser#cesu-8-mode), [Overlong Mode](Utf8Parser#overlong-mode), or when
+    /// skipping a byte order mark, since the decoded characters then can't be borrowed from
+    /// `bytes`.
+    ///
+    /// # Example
+    /// ```
+    /// use utf8_parser::{Utf8Parser, Utf8ParserError};
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// assert_eq!(parser.push_chunk(&[b'a', b'b', 0xc3]), Ok("ab"));
+    /// assert_eq!(parser.pop_pending(), None);
+    /// assert_eq!(parser.push_chunk(&[0xa9, b'c', b'd']), Ok("cd"));
+    /// assert_eq!(parser.pop_pending(), Some('é'));
+    /// assert_eq!(
+    ///     parser.push_chunk(&[b'e', 0xff, b'f']),
+    ///     Err((Utf8ParserError::InvalidByte(0xff), "e"))
+    /// );
+    /// ```
+    pub fn push_chunk<'a>(
+        &mut self,
+        bytes: &'a [u8],
+    ) -> Result<&'a str, (Utf8ParserError, &'a str)> {
+        assert!(
+            self.config.borrows_input(),
+            "push_chunk can't borrow characters in this parser's mode"
+        );
+        // At most the character completing a partial sequence is queued
+        if !self.pending.has_room(1) {
+            return Err((Utf8ParserError::OutputBufferFull, ""));
+        }
+        let mut bytes = bytes;
+        while self.in_progress() {
+            let Some((byte, rest)) = bytes.split_first() else {
+                return Ok("");
+            };
+            bytes = rest;
+            match self.push_char_spanned(*byte) {
+                Ok(Some((c, span))) => self.pending.push(c, span),
+                Ok(None) => {}
+                Err(err) => return Err((err, "")),
+            }
+        }
+        if self.config != Config::new() {
+            return self.push_chunk_checked(bytes);
+        }
+
+        let valid_up_to = match core::str::from_utf8(bytes) {
+            Ok(_) => bytes.len(),
+            Err(err) => err.valid_up_to(),
+        };
+        let (valid, rest) = bytes.split_at(valid_up_to);
+        let valid = core::str::from_utf8(valid).expect("valid_up_to is a char boundary");
+        self.bytes_consumed += valid.len() as u64;
+        self.seen_first_char |= !valid.is_empty();
+        #[cfg(feature = "debug-history")]
+        self.history.record(valid.as_bytes());
+        #[cfg(feature = "stats")]
+        for c in valid.chars() {
+            self.stats.record_char(c as u32);
+        }
+
+        // Either a partial sequence that the parser keeps, or an error before any character
+        for byte in rest {
+            let c = self.push(*byte).map_err(|err| (err, valid))?;
+            debug_assert!(c.is_none(), "decoded a character after valid_up_to");
+        }
+        Ok(valid)
+    }
+
+    // `push_chunk` in a mode that may reject some valid UTF-8, so every byte has to go through
+    // the parser. The characters it accepts are still encoded as UTF-8 in `bytes`.
+    fn push_chunk_checked<'a>(
+        &mut self,
+        bytes: &'a [u8],
+    ) -> Result<&'a str, (Utf8ParserError, &'a str)> {
+        let mut valid_up_to = 0;
+        let mut result = Ok(());
+        for (offset, byte) in bytes.iter().enumerate() {
+            match self.push(*byte) {
+                Ok(Some(_)) => valid_up_to = offset + 1,
+                Ok(None) => {}
+                Err(err) => {
+                    result = Err(err);
+                    break;
+                }
+            }
+        }
+        let valid = core::str::from_utf8(&bytes[..valid_up_to])
+            .expect("bytes were validated by the parser");
+        result.map(|()| valid).map_err(|err| (err, valid))
+    }
+
+    /// Push every byte in `bytes` into the parser, appending each decoded character to `sink`
+    ///
+    /// Stops at the first error, leaving the characters decoded before it in `sink`. A trailing
+    /// partial sequence is kept in the parser.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::Utf8Parser;
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// let mut decoded = String::new();
+    /// parser.push_into("hé".as_bytes(), &mut decoded)?;
+    /// parser.push_into(&[0xf0, 0x9f, 0x8e], &mut decoded)?;
+    /// parser.push_into(&[0x84], &mut decoded)?;
+    /// assert_eq!(decoded, "hé🎄");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn push_into<E: Extend<char>>(
+        &mut self,
+        bytes: &[u8],
+        sink: &mut E,
+    ) -> Result<(), Utf8ParserError> {
+        let mut chars = ['\0'; 64];
+        let mut bytes = bytes;
+        while let Some((byte, rest)) = bytes.split_first() {
+            let run = self.push_ascii(bytes, &mut chars);
+            if run > 0 {
+                sink.extend(chars[..run].iter().copied());
+                bytes = &bytes[run..];
+                continue;
+            }
+
+            sink.extend(self.push(*byte)?);
+            sink.extend(self.take_pending());
+            bytes = rest;
+        }
+        Ok(())
+    }
+
+    // Decode the run of ASCII bytes at the start of `bytes` into `out` without going through the
+    // state machine, returning how many were decoded. Must only be called with nothing pending.
+    fn push_ascii(&mut self, bytes: &[u8], out: &mut [char]) -> usize {
+        // CRLF mode needs to see every CR, some ASCII characters are controls, and a low enough
+        // limit rejects some ASCII
+        if !self.is_idle()
+            || self.config.coalesce_crlf
+            || self.config.reject_controls
+            || self.config.max_scalar < 0x7f
+        {
+            return 0;
+        }
+
+        let mut len = 0;
+        for (byte, slot) in bytes.iter().zip(out.iter_mut()) {
+            if !byte.is_ascii() {
+                break;
+            }
+            *slot = *byte as char;
+            len += 1;
+        }
+
+        self.bytes_consumed += len as u64;
+        self.seen_first_char |= len > 0;
+        #[cfg(feature = "debug-history")]
+        self.history.record(&bytes[..len]);
+        #[cfg(feature = "stats")]
+        {
+            self.stats.single += len as u64;
+        }
+        len
+    }
+
+    /// Decode a complete byte slice into a [String](alloc::string::String)
+    ///
+    /// This calls [Utf8Parser::finish] once all bytes are pushed, so truncated input is an error.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::{Utf8Parser, Utf8ParserError};
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// assert_eq!(parser.decode_all("h🎄".as_bytes())?, "h🎄");
+    /// assert_eq!(
+    ///     parser.decode_all(&[0xf0, 0x9f]),
+    ///     Err(Utf8ParserError::UnexpectedEndOfInput)
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn decode_all(&mut self, bytes: &[u8]) -> Result<alloc::string::String, Utf8ParserError> {
+        let mut decoded = alloc::string::String::new();
+        self.push_into(bytes, &mut decoded)?;
+        self.finish()?;
+        decoded.extend(self.take_pending());
+        Ok(decoded)
+    }
+
+    // `push` in lenient mode, yielding up to two characters in order, along with the bytes
+    // each came from
+    fn push_lenient(&mut self, byte: u8) -> (Option<SpannedChar>, Option<SpannedChar>) {
+        let span = Span::between(self.char_start, self.bytes_consumed);
+        if self.accepts(byte) {
+            // `accepts` has already ruled out every way this can fail
+            let c = match self.push_inner_impl(byte) {
+                // Only a surrogate in WTF-8 mode can fail to convert
+                Ok(Some(val)) => char::from_u32(val).or_else(|| self.replacement()),
+                Ok(None) => None,
+                Err(_) => {
+                    self.reset();
+                    self.replacement()
+                }
+            };
+            return (c.map(|c| (c, span)), None);
+        }
+
+        if self.is_idle() {
+            return (self.replacement().map(|c| (c, span)), None);
+        }
+
+        // The bytes so far are a maximal subpart, so replace them and start over with this byte.
+        // The parser is idle now, so that yields at most one character.
+        self.reset();
+        let byte_start = self.bytes_consumed - 1;
+        let replaced = Span::between(self.char_start, byte_start);
+        let replacement = self.replacement().map(|c| (c, replaced));
+        self.char_start = byte_start;
+        let (next, _) = self.push_lenient(byte);
+        (replacement, next)
+    }
+
+    // Substitute for malformed input in lenient mode
+    fn replacement(&mut self) -> Option<char> {
+        #[cfg(feature = "stats")]
+        self.stats.record_error();
+        Some(char::REPLACEMENT_CHARACTER)
+    }
+
+    // Whether `byte` can continue the current state towards a valid code point
+    //
+    // This is stricter than `push_inner_impl`, which only catches some errors once a sequence
+    // completes. The boundaries follow the UTF-8 decoder in the WHATWG Encoding Standard.
+    pub(crate) const fn accepts(&self, byte: u8) -> bool {
+        if matches!(self.core.state, State::Fresh)
+            && (self.config.rejects_start_byte(byte) || self.config.starts_too_long(byte))
+        {
+            return false;
+        }
+
+        let shift = Utf8ByteType::Continuation.payload_bits();
+        let byte = match ParsedByte::from_byte(byte) {
+            Ok(v) => v,
+            Err(_) => {
+                return false;
+            }
+        };
+
+        match (self.core.state, byte) {
+            (State::Fresh, ParsedByte::Single(_) | ParsedByte::StartTriple(_)) => true,
+            (State::Fresh, ParsedByte::StartDouble(value)) => {
+                self.config.allow_overlong
+                    || (value as u32) << shift >= FIRST_CODE_POINT_FOR_DOUBLE
+                    || (self.config.mutf8 && value == 0)
+            }
+            (State::Fresh, ParsedByte::StartQuadruple(value)) => {
+                !self.config.cesu8 && (value as u32) << (3 * shift) <= char::MAX as u32
+            }
+            (State::OneLeft(current), ParsedByte::ContinuationByte(value)) => {
+                // Only reachable with a short value in MUTF-8 or overlong mode
+                let val = push_byte(current, value);
+                self.config.allow_overlong || val >= FIRST_CODE_POINT_FOR_DOUBLE || val == 0
+            }
+            (State::TwoLeft(current), ParsedByte::ContinuationByte(value)) => {
+                let val = push_byte(current, value) << shift;
+                (self.config.allow_overlong || val >= FIRST_CODE_POINT_FOR_TRIPLE)
+                    && (self.config.wtf8 || self.config.cesu8 || !is_surrogate(val))
+            }
+            (State::ThreeLeft(current), ParsedByte::ContinuationByte(value)) => {
+                let val = push_byte(current, value) << (2 * shift);
+                (self.config.allow_overlong || val >= FIRST_CODE_POINT_FOR_QUADRUPLE)
+                    && val <= char::MAX as u32
+            }
+            _ => false,
+        }
+    }
+
+    // The error for a byte that `accepts` rejects, without pushing it
+    //
+    // This is the error strict decoding returns for `byte`, if any. Otherwise, the bytes so far
+    // can only complete an overlong encoding, a surrogate, or a code point above U+10FFFF, which
+    // strict decoding only reports once the sequence completes. Those are reported with the
+    // smallest code point the sequence could have encoded.
+    pub(crate) const fn rejection(&self, byte: u8) -> Utf8ParserError {
+        if let Err(err) = self.core.step(&self.config, byte) {
+            return err;
+        }
+
+        let shift = Utf8ByteType::Continuation.payload_bits();
+        let (val, first_code_point) = match (self.core.state, ParsedByte::from_byte(byte)) {
+            (State::Fresh, Ok(ParsedByte::StartDouble(value))) => {
+                ((value as u32) << shift, FIRST_CODE_POINT_FOR_DOUBLE)
+            }
+            (State::Fresh, Ok(ParsedByte::StartQuadruple(value))) => (
+                (value as u32) << (3 * shift),
+                FIRST_CODE_POINT_FOR_QUADRUPLE,
+            ),
+            (State::TwoLeft(current), Ok(ParsedByte::ContinuationByte(value))) => (
+                push_byte(current, value) << shift,
+                FIRST_CODE_POINT_FOR_TRIPLE,
+            ),
+            (State::ThreeLeft(current), Ok(ParsedByte::ContinuationByte(value))) => (
+                push_byte(current, value) << (2 * shift),
+                FIRST_CODE_POINT_FOR_QUADRUPLE,
+            ),
+            _ => return Utf8ParserError::InvalidByte(byte),
+        };
+        if val < first_code_point {
+            Utf8ParserError::OverlongEncoding
+        } else if is_surrogate(val) {
+            Utf8ParserError::SurrogateCodePoint(val)
+        } else {
+            Utf8ParserError::InvalidChar(val)
+        }
+    }
+
+    // Inner functionality of `push`
+    fn push_inner_impl(&mut self, byte: u8) -> Result<Option<u32>, Utf8ParserError> {
+        // Callers reset the parser on error, so there's nothing to write back
+        let (core, val) = self.core.step(&self.config, byte)?;
+        self.core = core;
+
+        #[cfg(feature = "stats")]
+        if let Some(val) = val {
+            self.stats.record_char(val);
+        }
+        Ok(val)
+    }
+
+    /// Number of bytes pushed into the parser since it was constructed
+    ///
+    /// Unlike the rest of the parser's state, this is not reset on errors. This can be used to
+    /// locate an error in a stream.
+    ///
+    /// # Example
+    /// ```
+    /// use utf8_parser::Utf8Parser;
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// for byte in [b'a', b'b', 0xff] {
+    ///     if parser.push(byte).is_err() {
+    ///         assert_eq!(parser.position() - 1, 2);
+    ///     }
+    /// }
+    /// assert_eq!(parser.position(), 3);
+    /// ```
+    pub const fn position(&self) -> u64 {
+        self.bytes_consumed
+    }
+
+    /// The kind of byte the parser expects next
+    pub const fn expected(&self) -> ExpectedByte {
+        match self.core.state {
+            State::Fresh => ExpectedByte::StartByte,
+            State::OneLeft(_) | State::TwoLeft(_) | State::ThreeLeft(_) => {
+                ExpectedByte::Continuation
+            }
+        }
+    }
+
+    /// The last few bytes pushed into the parser, oldest first
+    ///
+    /// Up to 8 bytes are kept, including any that caused errors. This is useful for reporting
+    /// the context of an error.
+    ///
+    /// # Example
+    /// ```
+    /// use utf8_parser::Utf8Parser;
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// let mut error_context = None;
+    /// for byte in "Thé 🦊".bytes().chain([0xff]) {
+    ///     if parser.push(byte).is_err() {
+    ///         error_context = Some(parser.recent_bytes().to_vec());
+    ///     }
+    /// }
+    /// assert_eq!(error_context.unwrap(), [0xc3, 0xa9, b' ', 0xf0, 0x9f, 0xa6, 0x8a, 0xff]);
+    /// ```
+    #[cfg(feature = "debug-history")]
+    pub fn recent_bytes(&self) -> &[u8] {
+        self.history.as_slice()
+    }
+
+    /// Statistics about what the parser has decoded over its lifetime
+    #[cfg(feature = "stats")]
+    pub const fn stats(&self) -> &Utf8Stats {
+        &self.stats
+    }
+
+    /// Number of continuation bytes the parser is still waiting for
+    ///
+    /// In [CESU-8 Mode](Utf8Parser#cesu-8-mode), a character is still in progress after its high
+    /// surrogate, so this counts the 3 bytes of the low surrogate as well.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::Utf8Parser;
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// assert_eq!(parser.remaining(), 0);
+    /// parser.push(0xf0)?;
+    /// assert_eq!(parser.remaining(), 3);
+    /// parser.push(0x9f)?;
+    /// assert_eq!(parser.remaining(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub const fn remaining(&self) -> usize {
+        match self.core.state {
+            State::Fresh if self.core.high_surrogate.is_some() => 3,
+            State::Fresh => 0,
+            State::OneLeft(_) => 1,
+            State::TwoLeft(_) => 2,
+            State::ThreeLeft(_) => 3,
+        }
+    }
+
+    /// The payload bits of the in-progress sequence's bytes that have been pushed so far
+    ///
+    /// Returns `None` if the parser is between sequences. The value is not shifted into place:
+    /// the low 6 × [Utf8Parser::remaining] bits of the code point are still missing, so the
+    /// finished code point will be `(partial_value << (6 * remaining)) | missing_bits`.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::Utf8Parser;
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// assert_eq!(parser.partial_value(), None);
+    /// // '€' is U+20AC, or 0b0010_000010_101100
+    /// parser.push(0xe2)?;
+    /// assert_eq!(parser.partial_value(), Some(0b0010));
+    /// parser.push(0x82)?;
+    /// assert_eq!(parser.partial_value(), Some(0b0010_000010));
+    /// assert_eq!(parser.remaining(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub const fn partial_value(&self) -> Option<u32> {
+        match self.core.state {
+            State::Fresh => None,
+            State::OneLeft(value) | State::TwoLeft(value) | State::ThreeLeft(value) => Some(value),
+        }
+    }
+
+    /// Reconstruct the bytes of the in-progress sequence that have been pushed so far
+    ///
+    /// Returns an empty slice if the parser is between sequences. In
+    /// [CESU-8 Mode](Utf8Parser#cesu-8-mode), this does not include a high surrogate waiting for
+    /// its pair.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::Utf8Parser;
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// parser.push(0xf0)?;
+    /// parser.push(0x9f)?;
+    /// let mut buffer = [0; 4];
+    /// assert_eq!(parser.partial_bytes(&mut buffer), [0xf0, 0x9f]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn partial_bytes<'a>(&self, out: &'a mut [u8; 4]) -> &'a [u8] {
+        let value = match self.core.state {
+            State::Fresh => {
+                return &[];
+            }
+            State::OneLeft(value) | State::TwoLeft(value) | State::ThreeLeft(value) => value,
+        };
+        let len = self.core.sequence_len as usize;
+        encode::encode_prefix(value, len, len - self.remaining(), out)
+    }
+
+    /// Returns true if the parser is between characters
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::Utf8Parser;
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// assert!(parser.is_idle());
+    /// parser.push(0xc3)?;
+    /// assert!(!parser.is_idle());
+    /// parser.push(0xa9)?;
+    /// assert!(parser.is_idle());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub const fn is_idle(&self) -> bool {
+        self.core.is_idle()
+    }
+
+    /// Returns true if the parser is partway through a multi-byte character
+    ///
+    /// This is the inverse of [Utf8Parser::is_idle].
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::Utf8Parser;
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// assert!(!parser.in_progress());
+    /// parser.push(0xc3)?;
+    /// assert!(parser.in_progress());
+    /// parser.push(0xa9)?;
+    /// assert!(!parser.in_progress());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub const fn in_progress(&self) -> bool {
+        !self.is_idle()
+    }
+
+    /// Abandon any in-progress character
+    ///
+    /// This is what happens automatically on errors. The [position](Utf8Parser::position) is not
+    /// reset.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::Utf8Parser;
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// parser.push(0xf0)?;
+    /// parser.reset();
+    /// assert!(parser.is_idle());
+    /// assert_eq!(parser.push(b'a')?, Some('a'));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reset(&mut self) {
+        self.core.state = State::Fresh;
+        self.core.high_surrogate = None;
+    }
+
+    /// Return the parser to how it was when constructed, keeping its mode
+    ///
+    /// Unlike [Utf8Parser::reset], this also resets the [position](Utf8Parser::position), and
+    /// discards pending characters, stats, and byte history. This lets a pooled parser be
+    /// reused for unrelated input without constructing a new one.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::Utf8Parser;
+    ///
+    /// let mut parser = Utf8Parser::new_coalesce_crlf();
+    /// parser.push(b'a')?;
+    /// parser.push(b'\r')?;
+    /// parser.reset_to_fresh();
+    /// assert_eq!(parser.position(), 0);
+    /// assert_eq!(parser.push(b'\n')?, Some('\n'));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reset_to_fresh(&mut self) {
+        *self = Self::with_config(self.config);
+    }
+
+    /// Take a snapshot of the parser, then [reset it](Utf8Parser::reset_to_fresh)
+    ///
+    /// This is [Utf8Parser::save_state] and [Utf8Parser::reset_to_fresh] in one call, for
+    /// recycling a parser while keeping its progress to resume later.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::Utf8Parser;
+    ///
+    /// let mut parser = Utf8Parser::new();
+    /// parser.push(0xc3)?;
+    /// let state = parser.take_state();
+    /// assert!(parser.is_idle());
+    ///
+    /// let mut resumed = Utf8Parser::from_state(state);
+    /// assert_eq!(resumed.push(0xa9)?, Some('é'));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn take_state(&mut self) -> Utf8ParserState {
+        let state = self.save_state();
+        self.reset_to_fresh();
+        state
+    }
+}
+
+impl Default for Utf8Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A snapshot of a [Utf8Parser], for resuming parsing later
+///
+/// Created by [Utf8Parser::save_state], and restored with [Utf8Parser::from_state]. This holds
+/// the parser's mode, position, and any partially-decoded character, but not its stats. With the
+/// `serde` feature, it can be serialized.
+///
+/// States compare equal if the parsers they came from would behave identically from that point
+/// on, and have the same position.
+///
+/// Deserialized states are checked, and rejected if no parser could have been in them.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        try_from = "snapshot::SerializedState",
+        into = "snapshot::SerializedState"
+    )
+)]
+pub struct Utf8ParserState {
+    core: Core,
+    config: Config,
+    pending: PendingChars,
+    bytes_consumed: u64,
+    seen_first_char: bool,
+    held_cr: Option<Span>,
+    sequence_start: u64,
+    char_start: u64,
+}
+
+impl Utf8ParserState {
+    /// The state of a parser that's partway through a `len`-byte sequence, waiting for
+    /// `remaining` more continuation bytes
+    ///
+    /// `value` holds the payload bits of the bytes pushed so far. Returns `None` if `len` isn't
+    /// 2 to 4, `remaining` isn't between 1 and `len - 1`, `value` has more bits than those bytes
+    /// hold, or the bytes so far are already an overlong encoding. The parser is otherwise in
+    /// its default mode.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+    /// use utf8_parser::{Utf8Parser, Utf8ParserState};
+    ///
+    /// // 0xe2 0x82, the start of '€'
+    /// let state = Utf8ParserState::partial(3, 1, 0b0010_000010).unwrap();
+    /// let mut parser = Utf8Parser::from_state(state);
+    /// assert_eq!(parser.push(0xac)?, Some('€'));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub const fn partial(len: usize, remaining: usize, value: u32) -> Option<Self> {
+        let Some(state) = partial_state(len, remaining, value, false) else {
+            return None;
+        };
+        let mut parser = Utf8Parser::new();
+        parser.core.state = state;
+        parser.core.sequence_len = len as u8;
+        Some(parser.save_state())
+    }
+
+    /// This state with any partially-decoded character discarded, as by [Utf8Parser::reset]
+    pub const fn reset(mut self) -> Self {
+        self.core.state = State::Fresh;
+        self.core.high_surrogate = None;
+        self.char_start = 0;
+        self
+    }
+}
+
+// The state partway through a `len`-byte sequence with `remaining` bytes left, if `value` could
+// have come from the bytes so far. See `Utf8ParserState::partial`.
+const fn partial_state(
+    len: usize,
+    remaining: usize,
+    value: u32,
+    allow_overlong: bool,
+) -> Option<State> {
+    let (state, start, first_code_point) = match (len, remaining) {
+        (2, 1) => (
+            State::OneLeft(value),
+            Utf8ByteType::Double,
+            FIRST_CODE_POINT_FOR_DOUBLE,
+        ),
+        (3, 1) => (
+            State::OneLeft(value),
+            Utf8ByteType::Triple,
+            FIRST_CODE_POINT_FOR_TRIPLE,
+        ),
+        (3, 2) => (
+            State::TwoLeft(value),
+            Utf8ByteType::Triple,
+            FIRST_CODE_POINT_FOR_TRIPLE,
+        ),
+        (4, 1) => (
+            State::OneLeft(value),
+            Utf8ByteType::Quadruple,
+            FIRST_CODE_POINT_FOR_QUADRUPLE,
+        ),
+        (4, 2) => (
+            State::TwoLeft(value),
+            Utf8ByteType::Quadruple,
+            FIRST_CODE_POINT_FOR_QUADRUPLE,
+        ),
+        (4, 3) => (
+            State::ThreeLeft(value),
+            Utf8ByteType::Quadruple,
+            FIRST_CODE_POINT_FOR_QUADRUPLE,
+        ),
+        _ => return None,
+    };
+
+    let shift = Utf8ByteType::Continuation.payload_bits() as usize;
+    let bits = start.payload_bits() as usize + shift * (len - 1 - remaining);
+    if value >> bits != 0 {
+        return None;
+    }
+    // `push` checks for overlong encodings once it's seen a continuation byte
+    if !allow_overlong && len - remaining >= 2 && value << (shift * remaining) < first_code_point {
+        return None;
+    }
+    Some(state)
+}
+
+/// Push a byte into a parser stored as a [Utf8ParserState], returning its next state
+///
+/// This is a functional-style [Utf8Parser::push], for callers that can store a small `Copy`
+/// value but not a `&mut Utf8Parser`. On error, the state to continue from is returned along with
+/// the error. Like [Utf8Parser::push], it has discarded the partial character and counted the
+/// rejected byte. Stats and history aren't stored in the state, so they aren't kept.
+///
+/// # Panics
+/// Panics if `state` is in [Lenient Mode](Utf8Parser#lenient-mode) or
+/// [CRLF Mode](Utf8Parser#crlf-mode), since a single byte can then produce more than one
+/// character.
+///
+/// # Example
+/// ```
+/// use utf8_parser::{step, Utf8Parser, Utf8ParserError};
+///
+/// let mut state = Utf8Parser::new().save_state();
+/// let mut decoded = String::new();
+/// for byte in "h🎄".bytes() {
+///     let (next, c) = step(state, byte).unwrap();
+///     state = next;
+///     decoded.extend(c);
+/// }
+/// assert_eq!(decoded, "h🎄");
+///
+/// let (err, state) = step(state, 0xff).unwrap_err();
+/// assert_eq!(err, Utf8ParserError::InvalidByte(0xff));
+/// assert_eq!(Utf8Parser::from_state(state).position(), 6);
+/// assert_eq!(step(state, b'a').unwrap().1, Some('a'));
+/// ```
+// The state is returned by value either way, and boxing it would need an allocator
+#[allow(clippy::result_large_err)]
+pub fn step(
+    state: Utf8ParserState,
+    byte: u8,
+) -> Result<(Utf8ParserState, Option<char>), (Utf8ParserError, Utf8ParserState)> {
+    assert!(
+        !state.config.lenient && !state.config.coalesce_crlf,
+        "step doesn't support lenient or CRLF mode"
+    );
+    let mut next = state;
+    if next.core.is_idle() {
+        next.char_start = next.bytes_consumed;
+    }
+    next.bytes_consumed += 1;
+    let val = match next.core.step(&next.config, byte) {
+        Ok((core, val)) => {
+            next.core = core;
+            val
+        }
+        Err(err) => return Err((err, next.reset())),
+    };
+    // Only meaningful partway through a character, as in `Utf8Parser::save_state`
+    if next.core.is_idle() {
+        next.char_start = 0;
+    }
+
+    let Some(val) = val else {
+        return Ok((next, None));
+    };
+    let first = !next.seen_first_char;
+    next.seen_first_char = true;
+    if next.config.skip_bom && first && val == BYTE_ORDER_MARK {
+        return Ok((next, None));
+    }
+    match char::from_u32(val) {
+        Some(c) => Ok((next, Some(c))),
+        // Only reachable in WTF-8 mode, where the surrogate has still been decoded
+        None => Err((Utf8ParserError::SurrogateCodePoint(val), next)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn conversion() -> Result<(), Utf8ParserError> {
+        let test_vectors = &[
+            (0x00, ParsedByte::Single(0x00)),
+            (0x01, ParsedByte::Single(0x01)),
+            (0x65, ParsedByte::Single(0x65)),
+            (0x7f, ParsedByte::Single(0x7f)),
+            (0b110_00000, ParsedByte::StartDouble(0)),
+            (0b110_00001, ParsedByte::StartDouble(0b1)),
+            (0b110_11001, ParsedByte::StartDouble(0b11001)),
+            (0b110_11111, ParsedByte::StartDouble(0b11111)),
+            (0b1110_0000, ParsedByte::StartTriple(0)),
+            (0b1110_0001, ParsedByte::StartTriple(0b1)),
+            (0b1110_1001, ParsedByte::StartTriple(0b1001)),
+            (0b1110_1111, ParsedByte::StartTriple(0b1111)),
+            (0b1111_0000, ParsedByte::StartQuadruple(0)),
+            (0b1111_0001, ParsedByte::StartQuadruple(0b1)),
+            (0b1111_0111, ParsedByte::StartQuadruple(0b111)),
+            (0x80, ParsedByte::ContinuationByte(0x00)),
+            (0x81, ParsedByte::ContinuationByte(0x01)),
+            (0b10_111111, ParsedByte::ContinuationByte(0b111111)),
+        ];
+
+        for tv in test_vectors.iter() {
+            assert_eq!(ParsedByte::from_byte(tv.0)?, tv.1);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn resume_from_every_state() -> Result<(), Utf8ParserError> {
+        let original = "aé€🎄";
+        for (make, input) in [
+            (
+                Utf8Parser::new as fn() -> Utf8Parser,
+                original.as_bytes().to_vec(),
+            ),
+            (Utf8Parser::new_cesu8, encode_cesu8(original)),
+        ] {
+            for split in 0..=input.len() {
+                let mut parser = make();
+                let mut decoded: String = parser.feed(&input[..split]).collect::<Result<_, _>>()?;
+
+                let mut parser = Utf8Parser::from_state(parser.save_state());
+                decoded.extend(
+                    parser
+                        .feed(&input[split..])
+                        .collect::<Result<Vec<_>, _>>()?,
+                );
+                parser.finish()?;
+                assert_eq!(decoded, original);
+                assert_eq!(parser.position(), input.len() as u64);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn partial_value_completes_to_char() -> Result<(), Utf8ParserError> {
+        for c in ['é', '€', '🎄', char::MAX] {
+            let mut buf = [0; 4];
+            let encoded = c.encode_utf8(&mut buf).as_bytes();
+            let mut parser = Utf8Parser::new();
+            for (i, byte) in encoded[..encoded.len() - 1].iter().enumerate() {
+                parser.push(*byte)?;
+                let missing = 6 * parser.remaining() as u32;
+                assert_eq!(parser.partial_value(), Some(c as u32 >> missing), "{c} {i}");
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn partial_state_matches_pushed_bytes() -> Result<(), Utf8ParserError> {
+        for c in ['é', '\u{7ff}', '€', '\u{800}', '🎄', char::MAX] {
+            let mut buf = [0; 4];
+            let encoded = c.encode_utf8(&mut buf).as_bytes();
+            for seen in 1..encoded.len() {
+                let mut pushed = Utf8Parser::new();
+                for byte in &encoded[..seen] {
+                    pushed.push(*byte)?;
+                }
+                let (State::OneLeft(value) | State::TwoLeft(value) | State::ThreeLeft(value)) =
+                    pushed.core.state
+                else {
+                    unreachable!();
+                };
+
+                let state = Utf8ParserState::partial(encoded.len(), encoded.len() - seen, value);
+                let mut parser = Utf8Parser::from_state(state.unwrap());
+                let mut partial = [0; 4];
+                assert_eq!(parser.partial_bytes(&mut partial), &encoded[..seen]);
+                let decoded: Result<Vec<_>, _> = parser.feed(&encoded[seen..]).collect();
+                assert_eq!(decoded?, [c]);
+            }
+        }
+
+        assert!(Utf8ParserState::partial(1, 0, 0).is_none());
+        assert!(Utf8ParserState::partial(2, 0, 0).is_none());
+        assert!(Utf8ParserState::partial(5, 1, 0).is_none());
+        assert!(Utf8ParserState::partial(2, 1, 0b11111).is_some());
+        assert!(Utf8ParserState::partial(2, 1, 0b111111).is_none());
+        // 0xe0 0x80 is overlong
+        assert!(Utf8ParserState::partial(3, 1, 0b0000_000000).is_none());
+        assert!(Utf8ParserState::partial(3, 2, 0b0000).is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn reachable_states() {
+        use std::collections::{BTreeSet, HashSet};
+
+        // Every state reachable from a fresh parser in one or two bytes, ignoring position
+        let mut reachable = HashSet::new();
+        for first in 0..=u8::MAX {
+            for second in 0..=u8::MAX {
+                let mut parser = Utf8Parser::new();
+                let _ = parser.push(first);
+                let _ = parser.push(second);
+                parser.bytes_consumed = 0;
+                reachable.insert(parser.save_state());
+            }
+        }
+        // Ord agrees with Eq
+        let sorted: BTreeSet<_> = reachable.iter().copied().collect();
+        assert_eq!(sorted.len(), reachable.len());
+        assert!(reachable.contains(&{
+            let mut parser = Utf8Parser::new();
+            parser.seen_first_char = true;
+            parser.save_state()
+        }));
+        assert!(reachable.contains(&Utf8ParserState::partial(4, 2, 0b000_011111).unwrap()));
+        assert!(!reachable.contains(&Utf8ParserState::partial(4, 1, 0b000_011111_001110).unwrap()));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_state() -> Result<(), Utf8ParserError> {
+        let mut parser = Utf8Parser::new_lenient();
+        parser.push(0xf0)?;
+        parser.push(0x9f)?;
+        let json = serde_json::to_string(&parser.save_state()).unwrap();
+        let state: Utf8ParserState = serde_json::from_str(&json).unwrap();
+
+        let mut parser = Utf8Parser::from_state(state);
+        assert_eq!(parser.push(0x8e)?, None);
+        assert_eq!(parser.push(b'a')?, Some('\u{fffd}'));
+        assert_eq!(parser.pop_pending(), Some('a'));
+        Ok(())
+    }
+
+    #[test]
+    fn push_chunk_at_every_boundary() -> Result<(), Utf8ParserError> {
+        let original = "aé€🎄b🦊";
+        let bytes = original.as_bytes();
+        for first in 0..=bytes.len() {
+            for second in first..=bytes.len() {
+                let mut parser = Utf8Parser::new();
+                let mut decoded = String::new();
+                for chunk in [&bytes[..first], &bytes[first..second], &bytes[second..]] {
+                    let borrowed = parser.push_chunk(chunk).map_err(|(err, _)| err)?;
+                    decoded.extend(parser.pop_pending());
+                    decoded.push_str(borrowed);
+                }
+                parser.finish()?;
+                assert_eq!(decoded, original);
+                assert_eq!(parser.position(), bytes.len() as u64);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn push_chunk_errors() {
+        let mut parser = Utf8Parser::new();
+        assert_eq!(
+            parser.push_chunk(b"ab\xffcd"),
+            Err((Utf8ParserError::InvalidByte(0xff), "ab"))
+        );
+        assert!(parser.is_idle());
+        assert_eq!(parser.position(), 3);
+        assert_eq!(parser.push_chunk(b"\xf0\x9f"), Ok(""));
+        assert_eq!(
+            parser.push_chunk(b"a"),
+            Err((Utf8ParserError::UnexpectedStartByte(b'a'), ""))
+        );
+        assert_eq!(parser.push_chunk(b"\xc3"), Ok(""));
+        assert_eq!(parser.finish(), Err(Utf8ParserError::UnexpectedEndOfInput));
+    }
+
+    #[test]
+    fn push_chunk_restricted_modes() {
+        let mut parser = Utf8Parser::new_reject_controls();
+        assert_eq!(parser.push_chunk("aé\t".as_bytes()), Ok("aé\t"));
+        assert_eq!(
+            parser.push_chunk("b🦊\x07c".as_bytes()),
+            Err((Utf8ParserError::ControlCharacter(7), "b🦊"))
+        );
+        assert_eq!(parser.push_chunk(&[0xe2, 0x82]), Ok(""));
+        assert_eq!(parser.push_chunk(&[0xac, b'd']), Ok("d"));
+        assert_eq!(parser.pop_pending(), Some('€'));
+
+        let mut parser = Utf8Parser::new_bmp_only();
+        assert_eq!(
+            parser.push_chunk("é🦊".as_bytes()),
+            Err((Utf8ParserError::CodePointTooLarge(0x1f98a), "é"))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "push_chunk can't borrow characters in this parser's mode")]
+    fn push_chunk_rejects_transforming_modes() {
+        let _ = Utf8Parser::new_coalesce_crlf().push_chunk(b"a\r\n");
+    }
+
+    #[test]
+    fn push_into_stops_at_error() {
+        let mut parser = Utf8Parser::new();
+        let mut decoded = Vec::new();
+        assert_eq!(
+            parser.push_into(b"ab\xffc", &mut decoded),
+            Err(Utf8ParserError::InvalidByte(0xff))
+        );
+        assert_eq!(decoded, ['a', 'b']);
+
+        let mut parser = Utf8Parser::new_lenient();
+        parser.push_into(b"\xe0\x80c", &mut decoded).unwrap();
+        assert_eq!(decoded, ['a', 'b', '\u{fffd}', '\u{fffd}', 'c']);
+    }
+
+    #[test]
+    fn coalesce_crlf() {
+        for (input, expected) in [
+            ("a\r\nb", "a\nb"),
+            ("\r\r\n\n", "\r\n\n"),
+            ("\n\r", "\n\r"),
+            ("\r🦊\r", "\r🦊\r"),
+            ("\r\r\r", "\r\r\r"),
+        ] {
+            let mut parser = Utf8Parser::new_coalesce_crlf();
+            let mut decoded: String = parser
+                .feed(input.as_bytes())
+                .collect::<Result<_, _>>()
+                .unwrap();
+            parser.finish().unwrap();
+            decoded.extend(parser.pop_pending());
+            assert_eq!(decoded, expected, "{input:?}");
+        }
+
+        // A CR before a truncated sequence is still flushed
+        let mut parser = Utf8Parser::new_coalesce_crlf();
+        let _ = parser.feed(b"\r\xc3").count();
+        assert_eq!(parser.finish(), Err(Utf8ParserError::UnexpectedEndOfInput));
+        assert_eq!(parser.pop_pending(), Some('\r'));
+
+        // And before an error, without joining a LF after it
+        let mut parser = Utf8Parser::new_coalesce_crlf();
+        assert_eq!(parser.push(b'\r'), Ok(None));
+        assert_eq!(parser.push(0xff), Err(Utf8ParserError::InvalidByte(0xff)));
+        assert_eq!(parser.pop_pending(), Some('\r'));
+        assert_eq!(parser.push(b'\n'), Ok(Some('\n')));
+        let decoded: Vec<_> = parser.feed(b"\r\xff\n").collect();
+        assert_eq!(
+            decoded,
+            [Ok('\r'), Err(Utf8ParserError::InvalidByte(0xff)), Ok('\n')]
+        );
+    }
+
+    #[test]
+    fn lenient_coalesce_crlf() {
+        let alphabet = [b'\r', b'\n', b'a', 0xc3, 0xa9, 0xe2, 0x82, 0xac, 0xff];
+        let mut rng = rand::thread_rng();
+        for _ in 0..10_000 {
+            let bytes: Vec<u8> = (0..rng.gen_range(0..10))
+                .map(|_| alphabet[rng.gen_range(0..alphabet.len())])
+                .collect();
+            let expected = String::from_utf8_lossy(&bytes).replace("\r\n", "\n");
+
+            let mut parser = Utf8Parser::builder()
+                .lenient(true)
+                .coalesce_crlf(true)
+                .build();
+            let mut decoded: String = parser.feed(&bytes).map(Result::unwrap).collect();
+            parser.finish().unwrap();
+            decoded.extend(core::iter::from_fn(|| parser.pop_pending()));
+            assert_eq!(decoded, expected, "{bytes:x?}");
+        }
+
+        // A held CR, a replacement, and the byte after it, all from one byte
+        let mut parser = Utf8Parser::builder()
+            .lenient(true)
+            .coalesce_crlf(true)
+            .build();
+        assert_eq!(parser.push(b'\r'), Ok(None));
+        assert_eq!(parser.push(0xc3), Ok(None));
+        assert_eq!(parser.push(b'a'), Ok(Some('\r')));
+        // Nothing left to queue, so there's room to finish
+        assert_eq!(parser.finish(), Ok(()));
+        assert_eq!(parser.pop_pending(), Some('\u{fffd}'));
+        assert_eq!(parser.pop_pending(), Some('a'));
+        assert_eq!(parser.pop_pending(), None);
+
+        // Without taking them, nothing pending is lost, and the next byte isn't consumed
+        assert_eq!(parser.push(b'\r'), Ok(None));
+        assert_eq!(parser.push(0xc3), Ok(None));
+        assert_eq!(parser.push(b'b'), Ok(Some('\r')));
+        assert_eq!(parser.push(b'c'), Err(Utf8ParserError::OutputBufferFull));
+        assert_eq!(parser.position(), 6);
+        assert_eq!(parser.pop_pending(), Some('\u{fffd}'));
+        assert_eq!(parser.pop_pending(), Some('b'));
+        assert_eq!(parser.push(b'c'), Ok(Some('c')));
+    }
+
+    #[test]
+    fn feed_positioned_across_calls() {
+        let original = "Thé quick brown 🦊 jamped over the lazy 🐕";
+        let bytes = original.as_bytes();
+        let expected: Vec<_> = original
+            .char_indices()
+            .map(|(offset, c)| (offset as u64, c))
+            .collect();
+        for split in 0..=bytes.len() {
+            let mut parser = Utf8Parser::new();
+            let (first, second) = bytes.split_at(split);
+            let mut positioned: Vec<_> =
+                parser.feed_positioned(first).map(Result::unwrap).collect();
+            positioned.extend(parser.feed_positioned(second).map(Result::unwrap));
+            assert_eq!(positioned, expected);
+        }
+
+        // Errors don't throw off later positions
+        let mut parser = Utf8Parser::new();
+        let positioned: Vec<_> = parser.feed_positioned(b"a\xc3b\xff\xc3\xa9").collect();
+        assert_eq!(
+            positioned,
+            [
+                Ok((0, 'a')),
+                Err(Utf8ParserError::UnexpectedStartByte(b'b')),
+                Err(Utf8ParserError::InvalidByte(0xff)),
+                Ok((4, 'é')),
+            ]
+        );
+    }
+
+    #[test]
+    fn feed_positioned_lenient() {
+        let mut parser = Utf8Parser::new_lenient();
+        let positioned: Vec<_> = parser
+            .feed_positioned(&[0xe0, 0x80, 0xf0, 0x9f, 0x41, 0xc3, 0xa9, 0xff])
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(
+            positioned,
+            [
+                (0, '\u{fffd}'),
+                (1, '\u{fffd}'),
+                (2, '\u{fffd}'),
+                (4, 'A'),
+                (5, 'é'),
+                (7, '\u{fffd}'),
+            ]
+        );
+
+        // Agrees with the lossy decoding of random input
+        let mut rng = rand::thread_rng();
+        for _ in 0..10_000 {
+            let bytes: Vec<u8> = (0..rng.gen_range(0..12))
+                .map(|_| {
+                    [b'a', 0x80, 0xbf, 0xc3, 0xe0, 0xed, 0xa0, 0xf0, 0x9f, 0xff]
+                        [rng.gen_range(0..10)]
+                })
+                .collect();
+            let mut parser = Utf8Parser::new_lenient();
+            let mut positioned: Vec<_> =
+                parser.feed_positioned(&bytes).map(Result::unwrap).collect();
+            parser.finish().unwrap();
+            let mut pending = parser.take_pending();
+            positioned.extend(
+                core::iter::from_fn(|| pending.pop_spanned()).map(|(c, span)| (span.start, c)),
+            );
+
+            // Each character starts where the bytes before it decode to the characters before it
+            for (index, (start, _)) in positioned.iter().enumerate() {
+                let prefix = String::from_utf8_lossy(&bytes[..*start as usize]);
+                let expected: String = positioned[..index].iter().map(|(_, c)| c).collect();
+                assert_eq!(prefix, expected, "{bytes:x?}");
+            }
+            let decoded: String = positioned.iter().map(|(_, c)| c).collect();
+            assert_eq!(decoded, String::from_utf8_lossy(&bytes), "{bytes:x?}");
+        }
+    }
+
+    #[test]
+    fn feed_positioned_crlf() {
+        let mut parser = Utf8Parser::new_coalesce_crlf();
+        let positioned: Vec<_> = parser
+            .feed_positioned("\ré\r\na\r".as_bytes())
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(positioned, [(0, '\r'), (1, 'é'), (3, '\n'), (5, 'a')]);
+        parser.finish().unwrap();
+        assert_eq!(
+            parser.pending.pop_spanned(),
+            Some(('\r', Span::between(6, 7)))
+        );
+
+        // A CR flushed by an error comes before it
+        let mut parser = Utf8Parser::new_coalesce_crlf();
+        let positioned: Vec<_> = parser.feed_positioned(b"\r\xffa").collect();
+        assert_eq!(
+            positioned,
+            [
+                Ok((0, '\r')),
+                Err(Utf8ParserError::InvalidByte(0xff)),
+                Ok((2, 'a'))
+            ]
+        );
+    }
+
+    #[test]
+    fn push_utf16_matches_encode_utf16() {
+        let original = "aé€\u{ffff}\u{10000}🎄\u{10ffff}";
+        let mut parser = Utf8Parser::new();
+        let mut out = [0; 2];
+        let mut units = Vec::new();
+        for byte in original.bytes() {
+            if let Some(encoded) = parser.push_utf16(byte, &mut out).unwrap() {
+                units.extend_from_slice(encoded);
+            }
+        }
+        assert_eq!(units, original.encode_utf16().collect::<Vec<_>>());
+        assert_eq!(&units[units.len() - 2..], [0xdbff, 0xdfff]);
+
+        assert_eq!(
+            parser.push_utf16(0xff, &mut out),
+            Err(Utf8ParserError::InvalidByte(0xff))
+        );
+    }
+
+    #[test]
+    fn try_push_keeps_state() {
+        let bytes = "é€🎄".as_bytes();
+        let mut parser = Utf8Parser::new();
+        for (offset, byte) in bytes.iter().enumerate() {
+            let before = parser.save_state();
+            for bad in [b'a', 0xc3, 0xff] {
+                if parser.is_idle() && bad != 0xff {
+                    continue;
+                }
+                assert!(parser.try_push(bad).is_err());
+                let mut after = parser.save_state();
+                assert_eq!(parser.position(), offset as u64 + 1);
+                after.bytes_consumed = before.bytes_consumed;
+                assert_eq!(after, before);
+                parser = Utf8Parser::from_state(before);
+            }
+            parser.try_push(*byte).unwrap();
+        }
+        assert!(parser.is_idle());
+
+        // CESU-8 keeps a high surrogate waiting across the error
+        let mut parser = Utf8Parser::new_cesu8();
+        let _ = parser.feed(&[0xed, 0xa0, 0xbc]).count();
+        assert!(parser.try_push(b'a').is_err());
+        let decoded: Result<String, _> = parser.feed(&[0xed, 0xbe, 0x84]).collect();
+        assert_eq!(decoded, Ok("🎄".into()));
+    }
+
+    #[test]
+    fn step_matches_push() {
+        let bytes = [
+            b'a', 0xc3, 0xa9, 0xf0, 0x9f, b'b', 0xff, 0xe2, 0x82, 0xac, 0xf0,
+        ];
+        let mut parser = Utf8Parser::new();
+        let mut state = parser.save_state();
+        for byte in bytes {
+            match (parser.push(byte), step(state, byte)) {
+                (Ok(expected), Ok((next, c))) => {
+                    assert_eq!(c, expected);
+                    state = next;
+                }
+                (Err(expected), Err((err, next))) => {
+                    assert_eq!(err, expected);
+                    state = next;
+                }
+                mismatch => panic!("{byte:#04x}: {mismatch:?}"),
+            }
+            assert_eq!(state, parser.save_state());
+        }
+    }
+
+    #[test]
+    fn step_matches_push_in_every_supported_mode() {
+        let mut rng = rand::thread_rng();
+        for make in [
+            Utf8Parser::new as fn() -> Utf8Parser,
+            Utf8Parser::new_cesu8,
+            Utf8Parser::new_skip_bom,
+            Utf8Parser::new_reject_controls,
+            Utf8Parser::new_bmp_only,
+            || Utf8Parser::builder().wtf8(true).mutf8(true).build(),
+        ] {
+            for _ in 0..1000 {
+                let mut parser = make();
+                let mut state = parser.save_state();
+                let bytes = (0..rng.gen_range(0..16)).map(|_| {
+                    *[
+                        0x00, b'a', 0x80, 0xa0, 0xbc, 0xbe, 0xbf, 0xc0, 0xc3, 0xed, 0xef, 0xf0,
+                        0xff,
+                    ]
+                    .get(rng.gen_range(0..13))
+                    .unwrap()
+                });
+                for byte in bytes {
+                    let expected = parser.push(byte);
+                    state = match step(state, byte) {
+                        Ok((next, c)) => {
+                            assert_eq!(Ok(c), expected);
+                            next
+                        }
+                        Err((err, next)) => {
+                            assert_eq!(Err(err), expected);
+                            next
+                        }
+                    };
+                    assert_eq!(state, parser.save_state());
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "step doesn't support lenient or CRLF mode")]
+    fn step_rejects_lenient_mode() {
+        let _ = step(Utf8Parser::new_lenient().save_state(), b'a');
+    }
+
+    #[test]
+    fn push_at_reports_sequence_start() {
+        let bytes = [
+            b'a', 0xc3, 0xa9, 0xe2, 0x82, b'b', 0xff, 0xf0, 0x9f, 0x8e, 0x84, 0x80,
+        ];
+        let mut parser = Utf8Parser::new();
+        let errors: Vec<_> = (100..)
+            .zip(bytes)
+            .filter_map(|(offset, byte)| parser.push_at(byte, offset).err())
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                (Utf8ParserError::UnexpectedStartByte(b'b'), 103),
+                (Utf8ParserError::InvalidByte(0xff), 106),
+                (Utf8ParserError::UnexpectedContinuationByte(0x80), 111),
+            ]
+        );
+
+        // CESU-8 surrogate pairs start at the high surrogate
+        let mut parser = Utf8Parser::new_cesu8();
+        let bytes = [0xed, 0xa0, 0xbc, 0xed, 0xa0, 0xbc];
+        let errors: Vec<_> = (0..)
+            .zip(bytes)
+            .filter_map(|(offset, byte)| parser.push_at(byte, offset).err())
+            .collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].1, 0);
+    }
+
+    #[test]
+    fn every_char_round_trips() {
+        // Char ranges skip the surrogates
+        let mut buf = [0; 4];
+        for c in '\0'..=char::MAX {
+            let (last, init) = c.encode_utf8(&mut buf).as_bytes().split_last().unwrap();
+            let mut parser = Utf8Parser::new();
+            for byte in init {
+                assert_eq!(parser.push(*byte), Ok(None), "{c:?}");
+            }
+            assert_eq!(parser.push(*last), Ok(Some(c)));
+            assert!(parser.is_idle());
+        }
+    }
+
+    #[test]
+    fn push_counted_matches_len_utf8() -> Result<(), Utf8ParserError> {
+        let mut parser = Utf8Parser::new();
+        for c in "aé€🎄\0\u{7f}\u{80}\u{7ff}\u{800}\u{ffff}\u{10000}\u{10ffff}".chars() {
+            let mut buf = [0; 4];
+            let mut counted = None;
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                assert_eq!(counted, None);
+                counted = parser.push_counted(byte)?;
+            }
+            assert_eq!(counted, Some((c, c.len_utf8())));
+        }
+
+        let mut parser = Utf8Parser::new_mutf8();
+        let counted: Vec<_> = [0xc0, 0x80, 0xed, 0xa0, 0xbc, 0xed, 0xbe, 0x84]
+            .into_iter()
+            .filter_map(|byte| parser.push_counted(byte).unwrap())
+            .collect();
+        assert_eq!(counted, [('\0', 2), ('🎄', 6)]);
+        Ok(())
+    }
+
+    #[test]
+    fn push_counted_lenient() -> Result<(), Utf8ParserError> {
+        let mut parser = Utf8Parser::new_lenient();
+        for (bytes, expected, pending) in [
+            (&[0xe0, 0x80][..], ('\u{fffd}', 1), Some('\u{fffd}')),
+            (&[0xf0, 0x9f, 0x41], ('\u{fffd}', 2), Some('A')),
+            (&[0xe2, 0x82, 0xff], ('\u{fffd}', 2), Some('\u{fffd}')),
+            (&[0xed, 0xa0], ('\u{fffd}', 1), Some('\u{fffd}')),
+            (&[0xe2, 0x82, 0xac], ('€', 3), None),
+        ] {
+            let (last, init) = bytes.split_last().unwrap();
+            for byte in init {
+                assert_eq!(parser.push_counted(*byte)?, None, "{bytes:x?}");
+            }
+            assert_eq!(parser.push_counted(*last)?, Some(expected), "{bytes:x?}");
+            assert_eq!(parser.pop_pending(), pending, "{bytes:x?}");
+            parser.finish()?;
+            assert_eq!(parser.pop_pending(), None);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn push_counted_crlf() -> Result<(), Utf8ParserError> {
+        let mut parser = Utf8Parser::new_coalesce_crlf();
+        let mut counted = Vec::new();
+        for byte in "\ré\r\n🎄".bytes() {
+            counted.extend(parser.push_counted(byte)?);
+            // 'é' is held back by the byte that released the '\r'
+            counted.extend(parser.pop_pending().map(|c| (c, 0)));
+        }
+        assert_eq!(counted, [('\r', 1), ('é', 0), ('\n', 2), ('🎄', 4)]);
+
+        // Invalid bytes between a CR and LF don't stretch either one
+        let mut bytes = vec![b'\r'];
+        bytes.extend([0xff; 300]);
+        bytes.push(b'\n');
+        let mut counted = Vec::new();
+        for byte in &bytes {
+            match parser.push_counted(*byte) {
+                Ok(c) => counted.extend(c),
+                Err(err) => assert_eq!(err, Utf8ParserError::InvalidByte(0xff)),
+            }
+            counted.extend(parser.pop_pending().map(|c| (c, 1)));
+        }
+        assert_eq!(counted, [('\r', 1), ('\n', 1)]);
+
+        let mut parser = Utf8Parser::builder()
+            .lenient(true)
+            .coalesce_crlf(true)
+            .build();
+        let mut counted = Vec::new();
+        for byte in &bytes {
+            counted.extend(parser.push_counted(*byte)?);
+            counted.extend(core::iter::from_fn(|| parser.pop_pending()).map(|c| (c, 1)));
+        }
+        assert_eq!(counted.len(), 302);
+        assert!(counted.iter().all(|(_, len)| *len == 1));
+
+        // Nor do bytes `try_push` rejected, though they count towards the position
+        let mut parser = Utf8Parser::new_coalesce_crlf();
+        for (bytes, expected) in [(&b"\r\n"[..], ('\n', 2)), ("é".as_bytes(), ('é', 2))] {
+            let (last, init) = bytes.split_last().unwrap();
+            for byte in init {
+                assert_eq!(parser.push_counted(*byte)?, None);
+                for _ in 0..300 {
+                    assert!(parser.try_push(0xff).is_err());
+                }
+            }
+            assert_eq!(parser.push_counted(*last)?, Some(expected));
+        }
+        assert_eq!(parser.position(), 604);
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 0xc3 to complete a character, got Ok(None)")]
+    fn expect_char_incomplete() {
+        let mut parser = Utf8Parser::new();
+        assert_eq!(parser.expect_char(b'a'), 'a');
+        parser.expect_char(0xc3);
+    }
+
+    #[test]
+    fn sequence_length_matches_char() -> Result<(), Utf8ParserError> {
+        assert_eq!(Utf8ByteType::Continuation.sequence_length(), None);
+        for c in ['a', 'é', '€', '🦊'] {
+            let mut buffer = [0; 4];
+            let first = c.encode_utf8(&mut buffer).as_bytes()[0];
+            assert_eq!(
+                Utf8ByteType::of(first)?.sequence_length(),
+                Some(c.len_utf8())
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn continuation_count_matches_parser() -> Result<(), Utf8ParserError> {
+        assert_eq!(Utf8ByteType::Continuation.continuation_count(), 0);
+        for byte in [b'a', 0xc3, 0xe2, 0xf0] {
+            let mut parser = Utf8Parser::new();
+            parser.push(byte)?;
+            assert_eq!(
+                Utf8ByteType::of(byte)?.continuation_count(),
+                parser.remaining()
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn len_from_first_byte_matches_char() {
+        for c in [
+            '\0',
+            'a',
+            'é',
+            '\u{7ff}',
+            '\u{800}',
+            '€',
+            '\u{ffff}',
+            '🎄',
+            char::MAX,
+        ] {
+            let mut buf = [0; 4];
+            let encoded = c.encode_utf8(&mut buf).as_bytes();
+            assert_eq!(utf8_len_from_first_byte(encoded[0]), Ok(c.len_utf8()));
+        }
+        for byte in 0x80..0xc0 {
+            assert_eq!(
+                utf8_len_from_first_byte(byte),
+                Err(Utf8ParserError::UnexpectedContinuationByte(byte))
+            );
+        }
+        assert_eq!(
+            utf8_len_from_first_byte(0xff),
+            Err(Utf8ParserError::InvalidByte(0xff))
+        );
+    }
+
+    #[test]
+    fn byte_type_table() {
+        for byte in 0..=u8::MAX {
+            assert_eq!(BYTE_TYPE_TABLE[byte as usize], Utf8ByteType::of(byte));
+        }
+    }
+
+    #[test]
+    fn five_or_more_leading_ones_are_invalid() {
+        for byte in 0xf8..=u8::MAX {
+            assert_eq!(
+                Utf8ByteType::of(byte),
+                Err(Utf8ParserError::InvalidByte(byte))
+            );
+
+            // Whether a start byte or a continuation byte is expected
+            for prefix in [&[][..], &[0xc3], &[0xe2, 0x82], &[0xf0, 0x9f, 0x8e]] {
+                let mut parser = Utf8Parser::new();
+                for b in prefix {
+                    parser.push(*b).unwrap();
+                }
+                assert_eq!(parser.push(byte), Err(Utf8ParserError::InvalidByte(byte)));
+                assert!(parser.is_idle());
+            }
+        }
+    }
+
+    #[test]
     fn basic() -> Result<(), Utf8ParserError> {
         let mut parser = Utf8Parser::default();
         assert_eq!(parser.push(b'h')?, Some('h'));
@@ -342,6 +3274,922 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn feed_across_slices() -> Result<(), Utf8ParserError> {
+        let original = "Thé quick brown 🦊 jamped over the lazy 🐕".as_bytes();
+        for split in 0..original.len() {
+            let mut parser = Utf8Parser::new();
+            let mut rebuilt = String::new();
+            for chunk in [&original[..split], &original[split..]] {
+                for c in parser.feed(chunk) {
+                    rebuilt.push(c?);
+                }
+            }
+            assert_eq!(rebuilt.as_bytes(), original);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn feed_recovers_after_error() {
+        let mut parser = Utf8Parser::new();
+        let results: Vec<_> = parser.feed(&[b'a', 0xf0, b'b', 0xff, b'c']).collect();
+        assert_eq!(
+            results,
+            [
+                Ok('a'),
+                Err(Utf8ParserError::UnexpectedStartByte(b'b')),
+                Err(Utf8ParserError::InvalidByte(0xff)),
+                Ok('c'),
+            ]
+        );
+        // Trailing partial sequence carries over to the next call
+        assert_eq!(parser.feed(&[0xc3]).count(), 0);
+        assert_eq!(parser.feed(&[0xa9]).collect::<Vec<_>>(), [Ok('é')]);
+
+        // Nothing is consumed past the last character taken
+        let taken: Vec<_> = parser.feed(b"ab\xc3\xa9c").take(2).collect();
+        assert_eq!(taken, [Ok('a'), Ok('b')]);
+        assert_eq!(parser.position(), 9);
+    }
+
+    #[test]
+    fn remaining_bytes() -> Result<(), Utf8ParserError> {
+        let mut parser = Utf8Parser::new();
+        for (byte, remaining) in [
+            (b'a', 0),
+            (0b110_00011, 1),
+            (0b10_101001, 0),
+            (0b1110_0010, 2),
+            (0b10_000010, 1),
+            (0b10_101100, 0),
+        ] {
+            parser.push(byte)?;
+            assert_eq!(parser.remaining(), remaining);
+        }
+
+        // Errors reset the count
+        parser.push(0xf0)?;
+        assert!(parser.push(b'a').is_err());
+        assert_eq!(parser.remaining(), 0);
+
+        // '🎄' as a surrogate pair
+        let mut parser = Utf8Parser::new_cesu8();
+        for (byte, remaining) in [
+            (0xed, 2),
+            (0xa0, 1),
+            (0xbc, 3),
+            (0xed, 2),
+            (0xbe, 1),
+            (0x84, 0),
+        ] {
+            parser.push(byte)?;
+            assert_eq!(parser.remaining(), remaining);
+            assert_eq!(parser.in_progress(), remaining != 0);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn take_state_recycles() -> Result<(), Utf8ParserError> {
+        let mut parser = Utf8Parser::builder().lenient(true).skip_bom(true).build();
+        let fresh = parser.save_state();
+        for byte in "\u{feff}a".bytes().chain([0xe0, 0x80, 0xc3]) {
+            parser.push(byte)?;
+        }
+        let state = parser.take_state();
+        assert_ne!(state, fresh);
+        assert_eq!(parser.save_state(), fresh);
+
+        // The mode survives, including skipping a leading byte order mark again
+        let decoded: String = parser
+            .feed("\u{feff}b".as_bytes())
+            .collect::<Result<_, _>>()?;
+        assert_eq!(decoded, "b");
+
+        let mut resumed = Utf8Parser::from_state(state);
+        assert_eq!(resumed.pop_pending(), Some('\u{fffd}'));
+        assert_eq!(resumed.push(0xa9)?, Some('é'));
+        Ok(())
+    }
+
+    #[test]
+    fn finish_resets_state() -> Result<(), Utf8ParserError> {
+        let mut parser = Utf8Parser::new();
+        for byte in "🦊".bytes().take(3) {
+            parser.push(byte)?;
+        }
+        assert_eq!(parser.finish(), Err(Utf8ParserError::UnexpectedEndOfInput));
+        assert!(parser.is_idle());
+        assert_eq!(parser.finish(), Ok(()));
+        assert_eq!(parser.push(b'a')?, Some('a'));
+        Ok(())
+    }
+
+    fn parse_lossy(original: &[u8]) -> String {
+        let mut parser = Utf8Parser::new_lenient();
+        let mut rebuilt: String = parser.feed(original).map(Result::unwrap).collect();
+        parser.finish().unwrap();
+        rebuilt.extend(parser.pop_pending());
+        rebuilt
+    }
+
+    #[test]
+    fn lenient_matches_from_utf8_lossy() {
+        let cases: &[&[u8]] = &[
+            b"hello",
+            "Thé quick brown 🦊".as_bytes(),
+            &[0xff],
+            &[0x80, 0x80],
+            &[0xe0, 0x80],
+            &[0xe0, b'a'],
+            &[0xc0, 0x80],
+            &[0xc1, 0xbf],
+            &[0xed, 0xa0, 0x80],
+            &[0xf0, 0x80, 0x80, 0x80],
+            &[0xf4, 0x90, 0x80, 0x80],
+            &[0xf5, 0x80],
+            &[0xf0, 0x9f, 0x8e],
+            &[0xf0, 0x9f, 0x8e, b'a'],
+            &[0xf0, 0x9f, 0xf0, 0x9f, 0x8e, 0x84],
+        ];
+        for case in cases {
+            assert_eq!(
+                parse_lossy(case),
+                String::from_utf8_lossy(case),
+                "{case:x?}"
+            );
+        }
+    }
+
+    #[test]
+    fn lenient_matches_from_utf8_lossy_fuzzed() {
+        // Bias towards bytes that are interesting to UTF-8
+        const INTERESTING: &[u8] = &[
+            0x00, b'a', 0x7f, 0x80, 0x8f, 0x90, 0x9f, 0xa0, 0xbf, 0xc0, 0xc1, 0xc2, 0xdf, 0xe0,
+            0xe1, 0xec, 0xed, 0xee, 0xef, 0xf0, 0xf1, 0xf3, 0xf4, 0xf5, 0xf7, 0xf8, 0xff,
+        ];
+        let mut rng = rand::thread_rng();
+        for _ in 0..10_000 {
+            let len = rng.gen_range(0..16);
+            let bytes: Vec<u8> = (0..len)
+                .map(|_| {
+                    if rng.gen() {
+                        INTERESTING[rng.gen_range(0..INTERESTING.len())]
+                    } else {
+                        rng.gen()
+                    }
+                })
+                .collect();
+            assert_eq!(
+                parse_lossy(&bytes),
+                String::from_utf8_lossy(&bytes),
+                "{bytes:x?}"
+            );
+        }
+    }
+
+    #[test]
+    fn position_survives_errors() {
+        let mut parser = Utf8Parser::new();
+        assert_eq!(parser.position(), 0);
+        let _ = parser.feed("é".as_bytes()).count();
+        assert_eq!(parser.position(), 2);
+        assert!(parser.push(0xff).is_err());
+        assert_eq!(parser.position(), 3);
+        parser.finish().unwrap();
+        assert_eq!(parser.position(), 3);
+
+        // Lenient mode counts each byte once, even when it's reprocessed
+        let mut parser = Utf8Parser::new_lenient();
+        let _ = parser.feed(&[0xe0, b'a']).count();
+        assert_eq!(parser.position(), 2);
+    }
+
+    #[test]
+    fn push_scalar_matches_push() {
+        let mut rng = rand::thread_rng();
+        let mut parser = Utf8Parser::new();
+        let mut scalar_parser = Utf8Parser::new();
+        for _ in 0..100_000 {
+            let byte = rng.gen();
+            assert_eq!(
+                parser.push(byte).map(|c| c.map(u32::from)),
+                scalar_parser.push_scalar(byte)
+            );
+        }
+    }
+
+    #[test]
+    fn error_on_surrogates() {
+        for (bytes, result) in [
+            ([0xed, 0x9f, 0xbf], Ok("\u{d7ff}".into())),
+            (
+                [0xed, 0xa0, 0x80],
+                Err(Utf8ParserError::SurrogateCodePoint(0xd800)),
+            ),
+            (
+                [0xed, 0xbf, 0xbf],
+                Err(Utf8ParserError::SurrogateCodePoint(0xdfff)),
+            ),
+            ([0xee, 0x80, 0x80], Ok("\u{e000}".into())),
+        ] {
+            assert_eq!(parse_str_by_bytes(&bytes), result);
+        }
+    }
+
+    #[test]
+    fn wtf8_surrogates() -> Result<(), Utf8ParserError> {
+        let mut parser = Utf8Parser::new_wtf8();
+        let mut code_points = Vec::new();
+        for byte in [
+            0xed, 0xa0, 0x80, b'a', 0xed, 0xbf, 0xbf, 0xf0, 0x9f, 0xa6, 0x8a,
+        ] {
+            code_points.extend(parser.push_wtf8(byte)?.map(CodePoint::to_u32));
+        }
+        assert_eq!(code_points, [0xd800, 0x61, 0xdfff, 0x1f98a]);
+
+        // `push` can't represent surrogates
+        for byte in [0xed, 0xa0] {
+            parser.push(byte)?;
+        }
+        assert_eq!(
+            parser.push(0x80),
+            Err(Utf8ParserError::SurrogateCodePoint(0xd800))
+        );
+        assert!(parser.is_idle());
+
+        // Other errors are still errors
+        parser.push_wtf8(0xe0)?;
+        assert_eq!(
+            parser.push_wtf8(0x80),
+            Err(Utf8ParserError::OverlongEncoding)
+        );
+        Ok(())
+    }
+
+    // Encode a string as CESU-8
+    fn encode_cesu8(original: &str) -> Vec<u8> {
+        original
+            .encode_utf16()
+            .flat_map(|unit| {
+                if unit < 0x80 {
+                    vec![unit as u8]
+                } else if unit < 0x800 {
+                    vec![0b110_00000 | (unit >> 6) as u8, 0x80 | (unit & 0x3f) as u8]
+                } else {
+                    vec![
+                        0b1110_0000 | (unit >> 12) as u8,
+                        0x80 | ((unit >> 6) & 0x3f) as u8,
+                        0x80 | (unit & 0x3f) as u8,
+                    ]
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cesu8_surrogate_pairs() -> Result<(), Utf8ParserError> {
+        let original = "Thé quick brown 🦊 jamped over the lazy 🐕";
+        let mut parser = Utf8Parser::new_cesu8();
+        let decoded: String = parser
+            .feed(&encode_cesu8(original))
+            .collect::<Result<_, _>>()?;
+        assert_eq!(decoded, original);
+        parser.finish()
+    }
+
+    #[test]
+    fn cesu8_errors() {
+        let mut parser = Utf8Parser::new_cesu8();
+        // Four-byte sequences
+        assert_eq!(parser.push(0xf0), Err(Utf8ParserError::InvalidByte(0xf0)));
+
+        // Unpaired low surrogate
+        let low = encode_cesu8("🦊").split_off(3);
+        let results: Vec<_> = parser.feed(&low).collect();
+        assert_eq!(results, [Err(Utf8ParserError::SurrogateCodePoint(0xdd8a))]);
+
+        // High surrogate followed by something other than a low surrogate
+        let mut high = encode_cesu8("🦊");
+        high.truncate(3);
+        assert_eq!(parser.feed(&high).count(), 0);
+        assert!(parser.in_progress());
+        assert_eq!(
+            parser.push(b'a'),
+            Err(Utf8ParserError::SurrogateCodePoint(0xd83e))
+        );
+        assert!(parser.is_idle());
+
+        // High surrogate at the end of input
+        assert_eq!(parser.feed(&high).count(), 0);
+        assert_eq!(parser.finish(), Err(Utf8ParserError::UnexpectedEndOfInput));
+    }
+
+    #[test]
+    fn mutf8_nul() -> Result<(), Utf8ParserError> {
+        let mut parser = Utf8Parser::new_mutf8();
+        let mut bytes = vec![b'a', 0xc0, 0x80, b'b'];
+        bytes.extend(encode_cesu8("🦊"));
+        let decoded: String = parser.feed(&bytes).collect::<Result<_, _>>()?;
+        assert_eq!(decoded, "a\0b🦊");
+
+        // Everything else overlong is still an error
+        for overlong in [&[0xc0, 0x81][..], &[0xc1, 0xbf], &[0xe0, 0x80, 0x80]] {
+            let result: Result<String, _> = parser.feed(overlong).collect();
+            assert_eq!(result, Err(Utf8ParserError::OverlongEncoding));
+        }
+
+        // The carve-out doesn't apply to other modes
+        let mut parser = Utf8Parser::new_cesu8();
+        parser.push(0xc0)?;
+        assert_eq!(parser.push(0x80), Err(Utf8ParserError::OverlongEncoding));
+        Ok(())
+    }
+
+    #[test]
+    fn interior_nul() {
+        let bytes = b"\0a\0\0\xc3\xa9\0";
+        let expected = "\0a\0\0é\0";
+        for mut parser in [
+            Utf8Parser::new(),
+            Utf8Parser::new_lenient(),
+            Utf8Parser::new_mutf8(),
+            Utf8Parser::new_cesu8(),
+            Utf8Parser::new_wtf8(),
+            Utf8Parser::new_coalesce_crlf(),
+            Utf8Parser::builder().strict_start_bytes(true).build(),
+        ] {
+            let decoded: Result<String, _> = parser.feed(bytes).collect();
+            assert_eq!(decoded.as_deref(), Ok(expected));
+            assert_eq!(parser.finish(), Ok(()));
+        }
+        assert_eq!(decode_str(bytes), Ok(expected));
+        assert!(is_valid_utf8(bytes));
+
+        // Only rejecting controls special-cases it
+        let mut parser = Utf8Parser::new_reject_controls();
+        assert_eq!(parser.push(0), Err(Utf8ParserError::ControlCharacter(0)));
+        assert_eq!(parser.push(b'a'), Ok(Some('a')));
+    }
+
+    #[test]
+    fn allow_overlong() {
+        let mut parser = Utf8Parser::new_allow_overlong();
+        for (bytes, expected) in [
+            (&[0xc0, 0x80][..], Ok(vec!['\0'])),
+            (&[0xc1, 0xbf], Ok(vec!['\u{7f}'])),
+            (&[0xe0, 0x80, 0xaf], Ok(vec!['/'])),
+            (&[0xe0, 0x9f, 0xbf], Ok(vec!['\u{7ff}'])),
+            (&[0xf0, 0x80, 0x80, 0xaf], Ok(vec!['/'])),
+            (&[0xf0, 0x8f, 0xbf, 0xbf], Ok(vec!['\u{ffff}'])),
+            // Surrogates, even overlong ones, are still rejected
+            (
+                &[0xf0, 0x8d, 0xa0, 0x80],
+                Err(Utf8ParserError::SurrogateCodePoint(0xd800)),
+            ),
+            (
+                &[0xf4, 0x90, 0x80, 0x80],
+                Err(Utf8ParserError::InvalidChar(0x110000)),
+            ),
+        ] {
+            let result: Result<Vec<_>, _> = parser.feed(bytes).collect();
+            assert_eq!(result, expected, "{bytes:x?}");
+        }
+
+        // Lenient mode agrees on what's acceptable
+        let mut parser = Utf8Parser::builder()
+            .allow_overlong(true)
+            .lenient(true)
+            .build();
+        let decoded: Result<String, _> = parser.feed(&[0xc0, 0xaf, 0xe0, 0x80, 0x80]).collect();
+        assert_eq!(decoded, Ok("/\0".into()));
+    }
+
+    #[test]
+    fn max_sequence_len() {
+        let mut parser = Utf8Parser::builder().max_sequence_len(2).build();
+        let decoded: Vec<_> = parser.feed("aé€🦊b".as_bytes()).collect();
+        assert_eq!(
+            decoded,
+            [
+                Ok('a'),
+                Ok('é'),
+                Err(Utf8ParserError::SequenceTooLong(0xe2)),
+                Err(Utf8ParserError::UnexpectedContinuationByte(0x82)),
+                Err(Utf8ParserError::UnexpectedContinuationByte(0xac)),
+                Err(Utf8ParserError::SequenceTooLong(0xf0)),
+                Err(Utf8ParserError::UnexpectedContinuationByte(0x9f)),
+                Err(Utf8ParserError::UnexpectedContinuationByte(0xa6)),
+                Err(Utf8ParserError::UnexpectedContinuationByte(0x8a)),
+                Ok('b'),
+            ]
+        );
+
+        // Bytes that can't start any sequence are still invalid
+        assert_eq!(parser.push(0xf8), Err(Utf8ParserError::InvalidByte(0xf8)));
+
+        let mut parser = Utf8Parser::builder()
+            .max_sequence_len(1)
+            .lenient(true)
+            .build();
+        let decoded: Result<String, _> = parser.feed("aé".as_bytes()).collect();
+        assert_eq!(decoded, Ok("a\u{fffd}\u{fffd}".into()));
+    }
+
+    #[test]
+    fn strict_start_bytes() {
+        for byte in [0xc0, 0xc1] {
+            let mut parser = Utf8Parser::builder().strict_start_bytes(true).build();
+            assert_eq!(parser.push(byte), Err(Utf8ParserError::InvalidByte(byte)));
+            assert!(parser.is_idle());
+
+            // Without the option, the error waits for the continuation byte
+            let mut parser = Utf8Parser::new();
+            assert_eq!(parser.push(byte), Ok(None));
+            assert_eq!(parser.push(0x80), Err(Utf8ParserError::OverlongEncoding));
+        }
+
+        for byte in 0xf5..=0xf7 {
+            let mut parser = Utf8Parser::builder().strict_start_bytes(true).build();
+            assert_eq!(parser.push(byte), Err(Utf8ParserError::InvalidByte(byte)));
+            assert!(parser.is_idle());
+
+            let mut parser = Utf8Parser::new();
+            assert_eq!(parser.push(byte), Ok(None));
+            let result: Result<Vec<_>, _> = parser.feed(&[0x80, 0x80, 0x80]).collect();
+            assert!(matches!(result, Err(Utf8ParserError::InvalidChar(_))));
+        }
+
+        // Every other start byte is unaffected
+        let original = "aé\u{7ff}€🎄\u{10ffff}";
+        let mut parser = Utf8Parser::builder().strict_start_bytes(true).build();
+        let decoded: Result<String, _> = parser.feed(original.as_bytes()).collect();
+        assert_eq!(decoded.as_deref(), Ok(original));
+
+        // MUTF-8 still needs 0xC0 for NUL
+        let mut parser = Utf8Parser::builder()
+            .mutf8(true)
+            .strict_start_bytes(true)
+            .build();
+        let decoded: Result<Vec<_>, _> = parser.feed(&[0xc0, 0x80]).collect();
+        assert_eq!(decoded, Ok(vec!['\0']));
+        assert_eq!(parser.push(0xc1), Err(Utf8ParserError::InvalidByte(0xc1)));
+
+        // Lenient mode replaces the start byte on its own
+        let mut parser = Utf8Parser::builder()
+            .strict_start_bytes(true)
+            .lenient(true)
+            .build();
+        let decoded: Result<String, _> = parser.feed(&[0xc0, b'a']).collect();
+        assert_eq!(decoded, Ok("\u{fffd}a".into()));
+    }
+
+    // Every combination of the modes that relax or tighten validation, checked against vectors
+    // that sit on the boundaries between them
+    #[test]
+    fn mode_matrix() {
+        use Utf8ParserError::*;
+
+        #[derive(Copy, Clone, Debug)]
+        struct Modes {
+            allow_overlong: bool,
+            wtf8: bool,
+            reject_noncharacters: bool,
+            reject_controls: bool,
+            strict_start_bytes: bool,
+        }
+
+        type Expected = fn(Modes) -> Result<u32, Utf8ParserError>;
+
+        fn control(m: Modes, val: u32) -> Result<u32, Utf8ParserError> {
+            match m.reject_controls {
+                true => Err(ControlCharacter(val)),
+                false => Ok(val),
+            }
+        }
+        fn surrogate(m: Modes, val: u32) -> Result<u32, Utf8ParserError> {
+            match m.wtf8 {
+                true => Ok(val),
+                false => Err(SurrogateCodePoint(val)),
+            }
+        }
+        fn noncharacter(m: Modes, val: u32) -> Result<u32, Utf8ParserError> {
+            match m.reject_noncharacters {
+                true => Err(Noncharacter(val)),
+                false => Ok(val),
+            }
+        }
+        fn overlong(m: Modes, then: Expected) -> Result<u32, Utf8ParserError> {
+            match m.allow_overlong {
+                true => then(m),
+                false => Err(OverlongEncoding),
+            }
+        }
+
+        // Vectors, and what each decodes to under the given modes
+        let vectors: [(&[u8], Expected); 13] = [
+            (b"a", |_| Ok(0x61)),
+            (&[0x1b], |m| control(m, 0x1b)),
+            (&[0xc0, 0xaf], |m| match m.strict_start_bytes {
+                true => Err(InvalidByte(0xc0)),
+                false => overlong(m, |_| Ok(0x2f)),
+            }),
+            (&[0xc1, 0xbf], |m| match m.strict_start_bytes {
+                true => Err(InvalidByte(0xc1)),
+                false => overlong(m, |m| control(m, 0x7f)),
+            }),
+            (&[0xe0, 0x80, 0x80], |m| overlong(m, |m| control(m, 0))),
+            (&[0xe0, 0x9f, 0xbf], |m| overlong(m, |_| Ok(0x7ff))),
+            (&[0xed, 0xa0, 0x80], |m| surrogate(m, 0xd800)),
+            (&[0xf0, 0x8d, 0xb0, 0x80], |m| {
+                overlong(m, |m| surrogate(m, 0xdc00))
+            }),
+            (&[0xef, 0xbf, 0xbf], |m| noncharacter(m, 0xffff)),
+            (&[0xf0, 0x8f, 0xbf, 0xbe], |m| {
+                overlong(m, |m| noncharacter(m, 0xfffe))
+            }),
+            (&[0xf4, 0x8f, 0xbf, 0xbf], |m| noncharacter(m, 0x10ffff)),
+            (&[0xf4, 0x90, 0x80, 0x80], |_| Err(InvalidChar(0x110000))),
+            (&[0xf5, 0x80, 0x80, 0x80], |m| match m.strict_start_bytes {
+                true => Err(InvalidByte(0xf5)),
+                false => Err(InvalidChar(0x140000)),
+            }),
+        ];
+
+        for bits in 0..1 << 5 {
+            let m = Modes {
+                allow_overlong: bits & 1 != 0,
+                wtf8: bits & 2 != 0,
+                reject_noncharacters: bits & 4 != 0,
+                reject_controls: bits & 8 != 0,
+                strict_start_bytes: bits & 16 != 0,
+            };
+            let builder = Utf8Parser::builder()
+                .allow_overlong(m.allow_overlong)
+                .wtf8(m.wtf8)
+                .reject_noncharacters(m.reject_noncharacters)
+                .reject_controls(m.reject_controls)
+                .strict_start_bytes(m.strict_start_bytes);
+
+            for (bytes, expected) in vectors {
+                let expected = expected(m);
+                let mut parser = builder.build();
+                let decoded = bytes
+                    .iter()
+                    .find_map(|byte| parser.push_wtf8(*byte).transpose())
+                    .unwrap()
+                    .map(CodePoint::to_u32);
+                assert_eq!(decoded, expected, "{bytes:x?} {m:?}");
+                assert!(parser.is_idle());
+
+                // Lenient mode agrees on what's malformed
+                let mut parser = builder.lenient(true).build();
+                let decoded: Vec<_> = parser.feed(bytes).map(Result::unwrap).collect();
+                match expected.ok().and_then(char::from_u32) {
+                    Some(c) => assert_eq!(decoded, [c], "{bytes:x?} {m:?}"),
+                    None => assert!(decoded.contains(&'\u{fffd}'), "{bytes:x?} {m:?}"),
+                }
+
+                // Nothing is lost behind a held CR. The builder rejects WTF-8 with CRLF mode.
+                if !m.wtf8 {
+                    let mut parser = builder.lenient(true).coalesce_crlf(true).build();
+                    let mut decoded: Vec<_> = parser.feed(b"\r").map(Result::unwrap).collect();
+                    decoded.extend(parser.feed(bytes).map(Result::unwrap));
+                    parser.finish().unwrap();
+                    decoded.extend(core::iter::from_fn(|| parser.pop_pending()));
+                    assert_eq!(decoded[0], '\r', "{bytes:x?} {m:?}");
+                    match expected.ok().and_then(char::from_u32) {
+                        Some(c) => assert_eq!(decoded[1..], [c], "{bytes:x?} {m:?}"),
+                        None => assert!(decoded[1..].contains(&'\u{fffd}'), "{bytes:x?} {m:?}"),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn skip_only_leading_bom() -> Result<(), Utf8ParserError> {
+        let bom = "\u{feff}".as_bytes();
+
+        let mut parser = Utf8Parser::new_skip_bom();
+        for byte in bom {
+            assert_eq!(parser.push(*byte)?, None);
+        }
+        let decoded: String = parser.feed(bom).collect::<Result<_, _>>()?;
+        assert_eq!(decoded, "\u{feff}");
+
+        // Not leading
+        let mut parser = Utf8Parser::new_skip_bom();
+        let decoded: String = parser
+            .feed("a\u{feff}".as_bytes())
+            .collect::<Result<_, _>>()?;
+        assert_eq!(decoded, "a\u{feff}");
+
+        // Errors aren't characters, so the BOM is still leading
+        let mut parser = Utf8Parser::new_skip_bom();
+        assert!(parser.push(0xff).is_err());
+        let decoded: String = parser.feed(bom).collect::<Result<_, _>>()?;
+        assert_eq!(decoded, "");
+
+        // Off by default
+        assert_eq!(parse_str_by_bytes(bom)?, "\u{feff}");
+        Ok(())
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn decode_all() {
+        let original = "Thé quick brown 🦊 jamped over the lazy 🐕";
+        let mut parser = Utf8Parser::new();
+        assert_eq!(parser.decode_all(original.as_bytes()).unwrap(), original);
+        assert_eq!(
+            parser.decode_all(&original.as_bytes()[..original.len() - 1]),
+            Err(Utf8ParserError::UnexpectedEndOfInput)
+        );
+
+        // Lenient mode replaces the truncated sequence
+        let mut parser = Utf8Parser::new_lenient();
+        assert_eq!(parser.decode_all(&[b'a', 0xf0, 0x9f]).unwrap(), "a\u{fffd}");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        for kind in [
+            Utf8ByteType::Continuation,
+            Utf8ByteType::Single,
+            Utf8ByteType::Double,
+            Utf8ByteType::Triple,
+            Utf8ByteType::Quadruple,
+        ] {
+            let json = serde_json::to_string(&kind).unwrap();
+            assert_eq!(serde_json::from_str::<Utf8ByteType>(&json).unwrap(), kind);
+        }
+
+        for err in [
+            Utf8ParserError::InvalidByte(0xff),
+            Utf8ParserError::InvalidChar(0x110000),
+            Utf8ParserError::SurrogateCodePoint(0xd800),
+            Utf8ParserError::Noncharacter(0xfffe),
+            Utf8ParserError::CodePointTooLarge(0x10000),
+            Utf8ParserError::ControlCharacter(0x00),
+            Utf8ParserError::UnexpectedStartByte(0x61),
+            Utf8ParserError::UnexpectedContinuationByte(0x3f),
+            Utf8ParserError::OverlongEncoding,
+            Utf8ParserError::SequenceTooLong(0xf0),
+            Utf8ParserError::OutputBufferFull,
+            Utf8ParserError::UnexpectedEndOfInput,
+        ] {
+            let json = serde_json::to_string(&err).unwrap();
+            assert_eq!(serde_json::from_str::<Utf8ParserError>(&json).unwrap(), err);
+        }
+        assert_eq!(
+            serde_json::to_string(&Utf8ParserError::InvalidByte(0xff)).unwrap(),
+            r#"{"InvalidByte":255}"#
+        );
+    }
+
+    #[test]
+    fn errors_are_ordered() {
+        use std::collections::BTreeMap;
+
+        let mut counts = BTreeMap::new();
+        let mut parser = Utf8Parser::new();
+        for byte in [0xff, 0x80, 0xfe, 0x81, 0xff] {
+            *counts.entry(parser.push(byte).unwrap_err()).or_insert(0) += 1;
+        }
+        assert_eq!(
+            counts.into_iter().collect::<Vec<_>>(),
+            [
+                (Utf8ParserError::InvalidByte(0xfe), 1),
+                (Utf8ParserError::InvalidByte(0xff), 2),
+                (Utf8ParserError::UnexpectedContinuationByte(0x80), 1),
+                (Utf8ParserError::UnexpectedContinuationByte(0x81), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn partial_bytes_of_every_prefix() -> Result<(), Utf8ParserError> {
+        for c in [
+            'a',
+            '\u{80}',
+            'é',
+            '\u{7ff}',
+            '\u{800}',
+            '€',
+            '\u{ffff}',
+            '🦊',
+            char::MAX,
+        ] {
+            let mut encoded = [0; 4];
+            let encoded = c.encode_utf8(&mut encoded).as_bytes();
+            let mut parser = Utf8Parser::new();
+            let mut buffer = [0; 4];
+            assert!(parser.partial_bytes(&mut buffer).is_empty());
+            for len in 1..encoded.len() {
+                parser.push(encoded[len - 1])?;
+                assert_eq!(parser.partial_bytes(&mut buffer), &encoded[..len]);
+            }
+            parser.push(*encoded.last().unwrap())?;
+            assert!(parser.partial_bytes(&mut buffer).is_empty());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn push_slice_through_small_buffer() -> Result<(), Utf8ParserError> {
+        let original = "Thé quick brown 🦊 jamped over the lazy 🐕";
+        for (chunk_size, out_size) in [(1, 1), (3, 2), (5, 8), (64, 3)] {
+            let mut parser = Utf8Parser::new();
+            let mut out = vec!['\0'; out_size];
+            let mut rebuilt = String::new();
+            for mut chunk in original.as_bytes().chunks(chunk_size) {
+                while !chunk.is_empty() {
+                    let (consumed, written) = parser
+                        .push_slice(chunk, &mut out)
+                        .map_err(|(err, ..)| err)?;
+                    rebuilt.extend(&out[..written]);
+                    chunk = &chunk[consumed..];
+                }
+            }
+            assert_eq!(rebuilt, original);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn ascii_fast_path_matches_push() {
+        // ASCII runs interrupted by multi-byte sequences, truncation and errors
+        for input in [
+            &b"abc\xc3\xa9def"[..],
+            b"abc\xc3",
+            b"\xc3abc",
+            b"ab\xffcd",
+            b"\xef\xbb\xbfab",
+            b"ab\xef\xbb\xbf",
+        ] {
+            for parser in [
+                Utf8Parser::new(),
+                Utf8Parser::new_skip_bom(),
+                Utf8Parser::builder().max_scalar(0x62).build(),
+            ] {
+                let mut expected_parser = parser.clone();
+                let expected: Vec<_> = input
+                    .iter()
+                    .filter_map(|byte| expected_parser.push(*byte).transpose())
+                    .collect();
+                let valid: Vec<char> = expected.iter().map_while(|c| c.ok()).collect();
+
+                let mut fed = parser.clone();
+                assert_eq!(fed.feed(input).collect::<Vec<_>>(), expected, "{input:x?}");
+                assert_eq!(fed.save_state(), expected_parser.save_state());
+
+                let mut sliced = parser.clone();
+                let mut out = vec!['\0'; input.len()];
+                match sliced.push_slice(input, &mut out) {
+                    Ok((consumed, written)) => {
+                        assert_eq!(consumed, input.len());
+                        assert_eq!(out[..written], valid, "{input:x?}");
+                        assert_eq!(sliced.position(), input.len() as u64);
+                    }
+                    Err((err, consumed, written)) => {
+                        assert_eq!(Some(&Err(err)), expected.get(valid.len()), "{input:x?}");
+                        assert_eq!(out[..written], valid, "{input:x?}");
+                        assert_eq!(sliced.position(), consumed as u64);
+                    }
+                }
+
+                let mut into = parser.clone();
+                let mut decoded = Vec::new();
+                let result = into.push_into(input, &mut decoded);
+                assert_eq!(decoded, valid, "{input:x?}");
+                assert_eq!(
+                    result.err(),
+                    expected.get(valid.len()).and_then(|c| c.err())
+                );
+                if result.is_ok() {
+                    assert_eq!(into.in_progress(), expected_parser.in_progress());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn push_slice_keeps_pending_output() {
+        let mut parser = Utf8Parser::new_lenient();
+        let mut out = ['\0'];
+        // 0xe0 0x80 is replaced with two characters, which don't both fit
+        assert_eq!(parser.push_slice(&[0xe0, 0x80, b'a'], &mut out), Ok((2, 1)));
+        assert_eq!(parser.push_slice(b"a", &mut out), Ok((0, 1)));
+        assert_eq!(out, ['\u{fffd}']);
+        assert_eq!(parser.push_slice(b"a", &mut out), Ok((1, 1)));
+        assert_eq!(out, ['a']);
+    }
+
+    #[test]
+    fn push_slice_counts_on_error() {
+        let mut parser = Utf8Parser::new();
+        let mut out = ['\0'; 8];
+        let mut bytes = &b"ab\xffc\xf0d\xc3"[..];
+        let mut decoded: Vec<char> = Vec::new();
+        let mut errors = Vec::new();
+        while !bytes.is_empty() {
+            let (consumed, written) = match parser.push_slice(bytes, &mut out) {
+                Ok(counts) => counts,
+                Err((err, consumed, written)) => {
+                    errors.push(err);
+                    (consumed, written)
+                }
+            };
+            decoded.extend(&out[..written]);
+            bytes = &bytes[consumed..];
+        }
+        assert_eq!(decoded, ['a', 'b', 'c']);
+        assert_eq!(
+            errors,
+            [
+                Utf8ParserError::InvalidByte(0xff),
+                Utf8ParserError::UnexpectedStartByte(b'd')
+            ]
+        );
+        assert!(parser.in_progress());
+    }
+
+    #[test]
+    fn reject_noncharacters() {
+        let noncharacters = [
+            '\u{fdd0}',
+            '\u{fdef}',
+            '\u{fffe}',
+            '\u{2ffff}',
+            '\u{10fffe}',
+        ];
+        let allowed = ['\u{fdcf}', '\u{fdf0}', '\u{fffd}', '\u{2fffd}', '\u{30000}'];
+
+        let mut parser = Utf8Parser::new_reject_noncharacters();
+        for c in noncharacters {
+            let result: Result<String, _> = parser.feed(c.to_string().as_bytes()).collect();
+            assert_eq!(result, Err(Utf8ParserError::Noncharacter(c as u32)));
+            assert!(parser.is_idle());
+        }
+        for c in allowed {
+            let result: Result<String, _> = parser.feed(c.to_string().as_bytes()).collect();
+            assert_eq!(result, Ok(c.to_string()));
+        }
+
+        // Off by default
+        for c in noncharacters {
+            assert_eq!(
+                parse_str_by_bytes(c.to_string().as_bytes()),
+                Ok(c.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn reject_controls() {
+        let mut parser = Utf8Parser::new_reject_controls();
+        let mut buf = [0; 4];
+        for c in '\0'..='\u{a0}' {
+            let result: Result<Vec<_>, _> =
+                parser.feed(c.encode_utf8(&mut buf).as_bytes()).collect();
+            if c.is_control() && !['\t', '\n', '\r'].contains(&c) {
+                assert_eq!(result, Err(Utf8ParserError::ControlCharacter(c as u32)));
+            } else {
+                assert_eq!(result, Ok(vec![c]));
+            }
+        }
+
+        // The ASCII fast path doesn't skip the check
+        let mut out = ['\0'; 4];
+        assert_eq!(
+            parser.push_slice(b"ab\x07c", &mut out),
+            Err((Utf8ParserError::ControlCharacter(0x07), 3, 2))
+        );
+    }
+
+    #[test]
+    fn bmp_only() -> Result<(), Utf8ParserError> {
+        let mut parser = Utf8Parser::new_bmp_only();
+        let decoded: String = parser
+            .feed("\u{ffff}€".as_bytes())
+            .collect::<Result<_, _>>()?;
+        assert_eq!(decoded, "\u{ffff}€");
+
+        for c in ['\u{10000}', '🦊', char::MAX] {
+            let result: Result<String, _> = parser.feed(c.to_string().as_bytes()).collect();
+            assert_eq!(result, Err(Utf8ParserError::CodePointTooLarge(c as u32)));
+        }
+
+        // Also applies to CESU-8 surrogate pairs
+        let mut parser = Utf8Parser::builder().cesu8(true).max_scalar(0xffff).build();
+        let result: Result<String, _> = parser.feed(&encode_cesu8("🦊")).collect();
+        assert_eq!(result, Err(Utf8ParserError::CodePointTooLarge(0x1f98a)));
+        Ok(())
+    }
+
     #[test]
     fn reset_state_after_error() {
         let mut parser = Utf8Parser::default();
@@ -395,6 +4243,8 @@ mod tests {
         ];
         let err_but_not_overlong: Vec<&[u8]> = vec![
             // Represent 0x110000 in four bytes
+            &[0b11110_100, 0b10_010000, 0b10_000000, 0b10_000000],
+            // Represent 0x180000 in four bytes
             &[0b11110_110, 0b10_000000, 0b10_000000, 0b10_000000],
         ];
 
@@ -420,6 +4270,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn max_code_point() {
+        let mut parser = Utf8Parser::new();
+        for byte in [0xf4, 0x8f, 0xbf] {
+            assert_eq!(parser.push(byte), Ok(None));
+        }
+        assert_eq!(parser.push(0xbf), Ok(Some('\u{10ffff}')));
+
+        // One past the end is too large to be a code point, not overlong
+        for byte in [0xf4, 0x90, 0x80] {
+            assert_eq!(parser.push(byte), Ok(None));
+        }
+        assert_eq!(
+            parser.push(0x80),
+            Err(Utf8ParserError::InvalidChar(0x110000))
+        );
+        assert!(parser.is_idle());
+    }
+
     #[test]
     fn random_input_dont_panic() {
         let mut parser = Utf8Parser::default();
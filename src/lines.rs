@@ -0,0 +1,158 @@
+use crate::{Utf8Parser, Utf8ParserError};
+use alloc::string::String;
+
+/// Decodes bytes into lines
+///
+/// Lines end with `\n` or `\r\n`, which aren't included in the yielded lines. An unterminated
+/// final line is held until [LineDecoder::finish].
+///
+/// # Example
+/// ```
+/// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+/// use utf8_parser::LineDecoder;
+///
+/// let mut decoder = LineDecoder::new();
+/// let lines: Result<Vec<_>, _> = decoder.feed(b"one\r\ntw").collect();
+/// assert_eq!(lines?, ["one"]);
+/// let lines: Result<Vec<_>, _> = decoder.feed(b"o\nthree").collect();
+/// assert_eq!(lines?, ["two"]);
+/// assert_eq!(decoder.finish()?.as_deref(), Some("three"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct LineDecoder {
+    parser: Utf8Parser,
+    line: String,
+}
+
+impl LineDecoder {
+    /// Construct a new line decoder
+    pub const fn new() -> Self {
+        Self::with_parser(Utf8Parser::new())
+    }
+
+    /// Construct a new line decoder that decodes with `parser`
+    ///
+    /// This allows decoding lines in any of the parser's modes, such as
+    /// [Lenient Mode](Utf8Parser#lenient-mode).
+    pub const fn with_parser(parser: Utf8Parser) -> Self {
+        Self {
+            parser,
+            line: String::new(),
+        }
+    }
+
+    /// Push a byte into the decoder, yielding a line if this byte completes one
+    pub fn push(&mut self, byte: u8) -> Result<Option<String>, Utf8ParserError> {
+        let mut completed = None;
+        if let Some(c) = self.parser.push(byte)? {
+            completed = self.store(c);
+        }
+        // A byte decodes to at most one character other than a replacement or a held CR, so at
+        // most one line can be completed
+        while let Some(c) = self.parser.pop_pending() {
+            completed = completed.or(self.store(c));
+        }
+        Ok(completed)
+    }
+
+    /// Push every byte in `bytes` into the decoder, yielding each completed line or error
+    pub fn feed<'a>(
+        &'a mut self,
+        bytes: &'a [u8],
+    ) -> impl Iterator<Item = Result<String, Utf8ParserError>> + 'a {
+        bytes
+            .iter()
+            .filter_map(move |byte| self.push(*byte).transpose())
+    }
+
+    /// Signal the end of input, yielding the final line if it's not empty
+    ///
+    /// Errors with [Utf8ParserError::UnexpectedEndOfInput] if the input ends partway through a
+    /// character, in which case the final line is discarded.
+    pub fn finish(&mut self) -> Result<Option<String>, Utf8ParserError> {
+        if let Err(err) = self.parser.finish() {
+            self.line.clear();
+            return Err(err);
+        }
+        while let Some(c) = self.parser.pop_pending() {
+            self.line.push(c);
+        }
+        if self.line.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(core::mem::take(&mut self.line)))
+    }
+
+    fn store(&mut self, c: char) -> Option<String> {
+        if c != '\n' {
+            self.line.push(c);
+            return None;
+        }
+        if self.line.ends_with('\r') {
+            self.line.pop();
+        }
+        Some(core::mem::take(&mut self.line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn lines(chunks: &[&[u8]]) -> Result<Vec<String>, Utf8ParserError> {
+        let mut decoder = LineDecoder::new();
+        let mut lines = Vec::new();
+        for chunk in chunks {
+            for line in decoder.feed(chunk) {
+                lines.push(line?);
+            }
+        }
+        lines.extend(decoder.finish()?);
+        Ok(lines)
+    }
+
+    #[test]
+    fn matches_str_lines() {
+        for text in [
+            "",
+            "a",
+            "\n",
+            "\n\n",
+            "a\nb",
+            "a\nb\n",
+            "a\r\nb\r\n",
+            "a\rb\n",
+            "a\r",
+            "🦊\r\n🐕\n\r\n",
+        ] {
+            let bytes = text.as_bytes();
+            let expected: Vec<_> = text.lines().collect();
+            for split in 0..=bytes.len() {
+                let (first, second) = bytes.split_at(split);
+                assert_eq!(lines(&[first, second]).unwrap(), expected, "{text:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn cr_at_boundary() {
+        assert_eq!(lines(&[b"a\r", b"\nb"]).unwrap(), ["a", "b"]);
+        assert_eq!(lines(&[b"a\r", b"b"]).unwrap(), ["a\rb"]);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            lines(&[b"a\n", b"b\xc3"]),
+            Err(Utf8ParserError::UnexpectedEndOfInput)
+        );
+
+        let mut decoder = LineDecoder::with_parser(Utf8Parser::new_lenient());
+        let lines: Vec<_> = decoder.feed(b"a\xff\nb\xc3\nc\xc3").collect();
+        assert_eq!(lines, [Ok("a\u{fffd}".into()), Ok("b\u{fffd}".into())]);
+        assert_eq!(decoder.finish(), Ok(Some("c\u{fffd}".into())));
+    }
+}
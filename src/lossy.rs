@@ -0,0 +1,217 @@
+use crate::{Utf8Parser, Utf8ParserError};
+
+/// A parser that passes errors to a callback, which decides what to replace them with
+///
+/// This generalizes [Lenient Mode](Utf8Parser#lenient-mode): the callback can return a
+/// replacement character, or `None` to skip the malformed input entirely.
+///
+/// Malformed input is split up the same way as in lenient mode: the callback is called once for
+/// each maximal subpart, and a byte that can't continue a sequence is decoded on its own after
+/// the callback is called for the bytes before it. An error for bytes that could only have
+/// encoded a surrogate or a code point above U+10FFFF holds the smallest code point they could
+/// have encoded.
+///
+/// # Example
+/// ```
+/// use utf8_parser::LossyParser;
+///
+/// let mut parser = LossyParser::new(|_| Some('?'));
+/// let decoded: String = parser.feed(&[b'a', 0xff, 0xc3, b'b']).collect();
+/// assert_eq!(decoded, "a??b");
+///
+/// let mut parser = LossyParser::new(|_| None);
+/// let decoded: String = parser.feed(&[b'a', 0xff, 0xc3, b'b']).collect();
+/// assert_eq!(decoded, "ab");
+/// ```
+#[derive(Clone, Debug)]
+pub struct LossyParser<F> {
+    parser: Utf8Parser,
+    callback: F,
+    pending: Option<char>,
+}
+
+impl<F: FnMut(Utf8ParserError) -> Option<char>> LossyParser<F> {
+    /// Construct a new parser that calls `callback` on each error
+    pub const fn new(callback: F) -> Self {
+        Self {
+            parser: Utf8Parser::new(),
+            callback,
+            pending: None,
+        }
+    }
+
+    /// Push a byte into the parser
+    ///
+    /// A single byte can complete both a replacement and a character, in which case the
+    /// character is held until it's retrieved with [LossyParser::pop_pending].
+    pub fn push(&mut self, byte: u8) -> Option<char> {
+        if self.parser.accepts(byte) {
+            return self
+                .parser
+                .push(byte)
+                .unwrap_or_else(|err| (self.callback)(err));
+        }
+
+        let replacement = (self.callback)(self.parser.rejection(byte));
+        if self.parser.is_idle() {
+            return replacement;
+        }
+
+        // The bytes so far are a maximal subpart, so start over with this byte. The parser is
+        // idle now, so this can't be interrupted again.
+        self.parser.reset();
+        let next = self.push(byte);
+        if replacement.is_none() {
+            return next;
+        }
+        self.pending = next;
+        replacement
+    }
+
+    /// Take the character held back by the last call to [LossyParser::push], if any
+    pub fn pop_pending(&mut self) -> Option<char> {
+        self.pending.take()
+    }
+
+    /// Push every byte in `bytes` into the parser, yielding each decoded character
+    pub fn feed<'a>(&'a mut self, bytes: &'a [u8]) -> impl Iterator<Item = char> + 'a {
+        bytes.iter().flat_map(move |byte| {
+            let c = self.push(*byte);
+            c.into_iter().chain(self.pop_pending())
+        })
+    }
+
+    /// Signal the end of input
+    ///
+    /// If the input ended partway through a character, this calls the callback with
+    /// [Utf8ParserError::UnexpectedEndOfInput] and returns its replacement.
+    pub fn finish(&mut self) -> Option<char> {
+        let err = self.parser.finish().err()?;
+        (self.callback)(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn callback_sees_every_error() {
+        let mut errors = Vec::new();
+        let mut parser = LossyParser::new(|err| {
+            errors.push(err);
+            None
+        });
+        let decoded: String = parser.feed(&[0x80, b'a', 0xc3, 0xc3, 0xa9, 0xf0]).collect();
+        assert_eq!(parser.finish(), None);
+        assert_eq!(decoded, "aé");
+        assert_eq!(
+            errors,
+            [
+                Utf8ParserError::UnexpectedContinuationByte(0x80),
+                Utf8ParserError::UnexpectedStartByte(0xc3),
+                Utf8ParserError::UnexpectedEndOfInput,
+            ]
+        );
+    }
+
+    #[test]
+    fn maximal_subparts() {
+        for (bytes, expected) in [
+            (
+                &[0xc3, 0xff][..],
+                &[Utf8ParserError::InvalidByte(0xff); 2][..],
+            ),
+            (
+                &[0xe0, 0x80],
+                &[
+                    Utf8ParserError::OverlongEncoding,
+                    Utf8ParserError::UnexpectedContinuationByte(0x80),
+                ],
+            ),
+            (
+                &[0xf0, 0x80, 0x80],
+                &[
+                    Utf8ParserError::OverlongEncoding,
+                    Utf8ParserError::UnexpectedContinuationByte(0x80),
+                    Utf8ParserError::UnexpectedContinuationByte(0x80),
+                ],
+            ),
+            (
+                &[0xed, 0xa0, 0x80],
+                &[
+                    Utf8ParserError::SurrogateCodePoint(0xd800),
+                    Utf8ParserError::UnexpectedContinuationByte(0xa0),
+                    Utf8ParserError::UnexpectedContinuationByte(0x80),
+                ],
+            ),
+            (
+                &[0xf4, 0x90, b'a'],
+                &[
+                    Utf8ParserError::InvalidChar(0x110000),
+                    Utf8ParserError::UnexpectedContinuationByte(0x90),
+                ],
+            ),
+            (&[0xc0, b'a'], &[Utf8ParserError::OverlongEncoding]),
+            (&[0xf5, b'a'], &[Utf8ParserError::InvalidChar(0x140000)]),
+        ] {
+            let mut errors = Vec::new();
+            let mut parser = LossyParser::new(|err| {
+                errors.push(err);
+                None
+            });
+            let decoded: String = parser.feed(bytes).collect();
+            assert_eq!(parser.finish(), None);
+            assert_eq!(errors, expected, "{bytes:x?}");
+            assert_eq!(
+                decoded,
+                String::from_utf8_lossy(bytes).replace('\u{fffd}', "")
+            );
+        }
+    }
+
+    #[test]
+    fn matches_lenient() {
+        const INTERESTING: &[u8] = &[
+            b'a', 0x80, 0x9f, 0xa0, 0xbf, 0xc0, 0xc1, 0xc2, 0xe0, 0xed, 0xef, 0xf0, 0xf4, 0xf5,
+            0xff,
+        ];
+        let mut rng = rand::thread_rng();
+        for _ in 0..10_000 {
+            let bytes: Vec<u8> = (0..rng.gen_range(0..16))
+                .map(|_| {
+                    if rng.gen() {
+                        INTERESTING[rng.gen_range(0..INTERESTING.len())]
+                    } else {
+                        rng.gen()
+                    }
+                })
+                .collect();
+
+            let mut lenient = Utf8Parser::new_lenient();
+            let mut expected: String = lenient.feed(&bytes).map(Result::unwrap).collect();
+            lenient.finish().unwrap();
+            expected.extend(lenient.pop_pending());
+
+            let mut parser = LossyParser::new(|_| Some(char::REPLACEMENT_CHARACTER));
+            let mut decoded: String = parser.feed(&bytes).collect();
+            decoded.extend(parser.finish());
+            assert_eq!(decoded, expected, "{bytes:x?}");
+        }
+    }
+
+    #[test]
+    fn valid_input_is_untouched() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            let original: String = (0..rng.gen_range(0..16))
+                .map(|_| rng.gen::<char>())
+                .collect();
+            let mut parser = LossyParser::new(|_| unreachable!());
+            let decoded: String = parser.feed(original.as_bytes()).collect();
+            assert_eq!(parser.finish(), None);
+            assert_eq!(decoded, original);
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use crate::{Utf8Parser, Utf8ParserError};
+
+// Longest sequence a parser can be partway through: a CESU-8 surrogate pair
+const MAX_RAW_LEN: usize = 6;
+
+/// A parser that also forwards the raw bytes of each valid character
+///
+/// The bytes of a sequence are held until it completes, then appended to the caller's buffer
+/// exactly as they were pushed, so partial and malformed sequences are never forwarded. This
+/// suits a transparent proxy that inspects the characters it passes along.
+///
+/// [Lenient Mode](Utf8Parser#lenient-mode) and [CRLF Mode](Utf8Parser#crlf-mode) change which
+/// characters come out of a byte, so the forwarded bytes wouldn't match the returned characters,
+/// and they aren't supported.
+///
+/// # Example
+/// ```
+/// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+/// use utf8_parser::{PassthroughParser, Utf8ParserError};
+///
+/// let mut parser = PassthroughParser::new();
+/// let mut forwarded = Vec::new();
+/// assert_eq!(parser.push(b'a', &mut forwarded)?, Some('a'));
+/// assert_eq!(parser.push(0xc3, &mut forwarded)?, None);
+/// assert_eq!(forwarded, b"a");
+/// assert_eq!(parser.push(0xa9, &mut forwarded)?, Some('é'));
+/// assert_eq!(forwarded, "aé".as_bytes());
+///
+/// // Malformed input is never forwarded
+/// assert_eq!(parser.push(0xe2, &mut forwarded)?, None);
+/// assert!(parser.push(b'b', &mut forwarded).is_err());
+/// assert_eq!(forwarded, "aé".as_bytes());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct PassthroughParser {
+    parser: Utf8Parser,
+    raw: [u8; MAX_RAW_LEN],
+    raw_len: usize,
+}
+
+impl PassthroughParser {
+    /// Construct a new parser
+    pub const fn new() -> Self {
+        Self::with_parser(Utf8Parser::new())
+    }
+
+    /// Construct a new parser that decodes with `parser`
+    ///
+    /// `parser` should be idle, since the bytes already pushed into it can't be forwarded.
+    ///
+    /// # Panics
+    /// Panics if `parser` is in [Lenient Mode](Utf8Parser#lenient-mode) or
+    /// [CRLF Mode](Utf8Parser#crlf-mode).
+    pub const fn with_parser(parser: Utf8Parser) -> Self {
+        assert!(
+            !parser.config.lenient && !parser.config.coalesce_crlf,
+            "PassthroughParser doesn't support lenient or CRLF mode"
+        );
+        Self {
+            parser,
+            raw: [0; MAX_RAW_LEN],
+            raw_len: 0,
+        }
+    }
+
+    /// Push a byte into the parser
+    ///
+    /// If the byte completes a character, the bytes of that character are appended to
+    /// `forward`. On error, the bytes of the malformed sequence are dropped.
+    pub fn push<E: Extend<u8>>(
+        &mut self,
+        byte: u8,
+        forward: &mut E,
+    ) -> Result<Option<char>, Utf8ParserError> {
+        // An idle parser held no bytes, so this can only be reached with room to spare
+        self.raw[self.raw_len] = byte;
+        self.raw_len += 1;
+
+        let result = self.parser.push(byte);
+        if let Ok(Some(_)) = result {
+            forward.extend(self.raw[..self.raw_len].iter().copied());
+        }
+        if self.parser.is_idle() {
+            // Either forwarded, malformed, or consumed without a character, such as a skipped
+            // byte order mark
+            self.raw_len = 0;
+        }
+        result
+    }
+
+    /// Signal the end of input, dropping a partial sequence
+    ///
+    /// Errors with [Utf8ParserError::UnexpectedEndOfInput] if the input ended partway through a
+    /// character.
+    pub fn finish(&mut self) -> Result<(), Utf8ParserError> {
+        self.raw_len = 0;
+        self.parser.finish()
+    }
+}
+
+impl Default for PassthroughParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn forwards_only_valid_characters() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..10_000 {
+            let bytes: Vec<u8> = (0..rng.gen_range(0..16))
+                .map(|_| {
+                    [b'a', 0x80, 0xa9, 0xc3, 0xe2, 0x82, 0xac, 0xf0, 0x9f, 0xff]
+                        [rng.gen_range(0..10)]
+                })
+                .collect();
+            let mut parser = PassthroughParser::new();
+            let mut forwarded = Vec::new();
+            let mut decoded = String::new();
+            for byte in &bytes {
+                if let Ok(Some(c)) = parser.push(*byte, &mut forwarded) {
+                    decoded.push(c);
+                }
+            }
+            let _ = parser.finish();
+            assert_eq!(forwarded, decoded.as_bytes(), "{bytes:x?}");
+        }
+    }
+
+    #[test]
+    fn forwards_original_encoding() {
+        // U+1F384, as a CESU-8 surrogate pair
+        let pair = [0xed, 0xa0, 0xbc, 0xed, 0xbe, 0x84];
+        let mut parser = PassthroughParser::with_parser(Utf8Parser::new_cesu8());
+        let mut forwarded = Vec::new();
+        let decoded: Vec<_> = pair
+            .iter()
+            .map(|byte| parser.push(*byte, &mut forwarded))
+            .collect();
+        assert_eq!(decoded[5], Ok(Some('🎄')));
+        assert_eq!(forwarded, pair);
+
+        // A skipped byte order mark isn't forwarded
+        let mut parser = PassthroughParser::with_parser(Utf8Parser::new_skip_bom());
+        let mut forwarded = Vec::new();
+        for byte in "\u{feff}a".bytes() {
+            parser.push(byte, &mut forwarded).unwrap();
+        }
+        assert_eq!(forwarded, b"a");
+    }
+
+    #[test]
+    #[should_panic(expected = "PassthroughParser doesn't support lenient or CRLF mode")]
+    fn rejects_lenient_mode() {
+        PassthroughParser::with_parser(Utf8Parser::new_lenient());
+    }
+
+    #[test]
+    #[should_panic(expected = "PassthroughParser doesn't support lenient or CRLF mode")]
+    fn rejects_crlf_mode() {
+        PassthroughParser::with_parser(Utf8Parser::new_coalesce_crlf());
+    }
+}
@@ -0,0 +1,154 @@
+// Most characters one push can queue. A byte can produce three characters, in CRLF mode a held
+// CR, then in lenient mode a replacement and the character after it, and the first is returned.
+pub(crate) const MAX_QUEUED: usize = 2;
+
+// Room for everything one push queues, plus a character the caller hasn't taken yet
+const PENDING_LEN: usize = 3;
+
+// The bytes a decoded character came from: `len` bytes starting at parser position `start`, not
+// counting any that `try_push` rejected in between
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Span {
+    pub(crate) start: u64,
+    pub(crate) len: u8,
+}
+
+impl Span {
+    // The span of the bytes from `start` up to, but not including, `end`
+    pub(crate) const fn between(start: u64, end: u64) -> Self {
+        let len = end - start;
+        // Only used in lenient mode, where nothing is rejected partway through a character
+        debug_assert!(len <= 6, "span is longer than any character");
+        Self {
+            start,
+            len: len as u8,
+        }
+    }
+
+    // Whether the span ends at or before parser position `end`
+    #[cfg(feature = "serde")]
+    pub(crate) const fn ends_by(self, end: u64) -> bool {
+        match self.start.checked_add(self.len as u64) {
+            Some(span_end) => span_end <= end,
+            None => false,
+        }
+    }
+}
+
+// A decoded character and the bytes it came from
+pub(crate) type SpannedChar = (char, Span);
+
+// Characters held back for `pop_pending`, oldest first, along with where they came from
+//
+// Unused slots are always '\0' with an empty span, so derived comparisons only see the queued
+// characters.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct PendingChars {
+    chars: [char; PENDING_LEN],
+    spans: [Span; PENDING_LEN],
+    len: u8,
+}
+
+impl PendingChars {
+    pub(crate) const fn new() -> Self {
+        Self {
+            chars: ['\0'; PENDING_LEN],
+            spans: [Span { start: 0, len: 0 }; PENDING_LEN],
+            len: 0,
+        }
+    }
+
+    // Whether `count` more characters can be queued
+    pub(crate) const fn has_room(&self, count: usize) -> bool {
+        self.len as usize + count <= PENDING_LEN
+    }
+
+    // Queue a character. Everything that queues checks for room first, so this can't overflow.
+    pub(crate) fn push(&mut self, c: char, span: Span) {
+        debug_assert!((self.len as usize) < PENDING_LEN);
+        self.chars[self.len as usize] = c;
+        self.spans[self.len as usize] = span;
+        self.len += 1;
+    }
+
+    // Whether this could have been queued by a parser at position `end`, for checking
+    // deserialized states
+    #[cfg(feature = "serde")]
+    pub(crate) fn is_valid(&self, end: u64) -> bool {
+        let len = self.len as usize;
+        len <= PENDING_LEN
+            && self.spans[..len].iter().all(|span| span.ends_by(end))
+            && self.chars[len..].iter().all(|c| *c == '\0')
+            && self.spans[len..]
+                .iter()
+                .all(|span| *span == Span::default())
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<char> {
+        self.pop_spanned().map(|(c, _)| c)
+    }
+
+    pub(crate) fn pop_spanned(&mut self) -> Option<SpannedChar> {
+        if self.len == 0 {
+            return None;
+        }
+        let popped = (self.chars[0], self.spans[0]);
+        self.chars.copy_within(1.., 0);
+        self.spans.copy_within(1.., 0);
+        self.len -= 1;
+        self.chars[self.len as usize] = '\0';
+        self.spans[self.len as usize] = Span::default();
+        Some(popped)
+    }
+}
+
+impl Iterator for PendingChars {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        self.pop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_in_first_out() {
+        let mut pending = PendingChars::new();
+        assert_eq!(pending.pop(), None);
+        pending.push('a', Span::between(0, 1));
+        pending.push('b', Span::between(1, 3));
+        assert_eq!(pending.pop(), Some('a'));
+        pending.push('c', Span::between(3, 4));
+        assert_eq!(pending.pop_spanned(), Some(('b', Span::between(1, 3))));
+        pending.push('d', Span::between(4, 5));
+        pending.push('e', Span::between(5, 6));
+        assert_eq!(pending.collect::<String>(), "cde");
+
+        // Popped characters leave no trace in comparisons
+        let mut pending = PendingChars::new();
+        for (c, start) in "abc".chars().zip(0..) {
+            pending.push(c, Span::between(start, start + 1));
+        }
+        assert_eq!(pending.pop(), Some('a'));
+        assert_eq!(pending.pop(), Some('b'));
+        let mut expected = PendingChars::new();
+        expected.push('c', Span::between(2, 3));
+        assert_eq!(pending, expected);
+    }
+
+    #[test]
+    fn room() {
+        let mut pending = PendingChars::new();
+        assert!(pending.has_room(PENDING_LEN));
+        pending.push('a', Span::between(0, 1));
+        pending.push('b', Span::between(1, 2));
+        assert!(pending.has_room(1) && !pending.has_room(2));
+        pending.pop();
+        assert!(pending.has_room(2));
+    }
+}
@@ -0,0 +1,203 @@
+use crate::{Utf8Parser, Utf8ParserError};
+
+/// A fixed-size read buffer that decodes UTF-8 across reads
+///
+/// Read into [Utf8Reframer::spare], then call [Utf8Reframer::commit] with the number of bytes
+/// read to get every complete character as a `&str`. Up to 3 bytes of a sequence that's split
+/// between reads are carried over and prepended to the next read.
+///
+/// `N` must be at least 4.
+///
+/// # Example
+/// ```
+/// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+/// use utf8_parser::Utf8Reframer;
+///
+/// let mut reframer = Utf8Reframer::<8>::new();
+/// let mut reads = [&[b'a', 0xf0, 0x9f][..], &[0x8e, 0x84, b'b']].into_iter();
+///
+/// let read = reads.next().unwrap();
+/// reframer.spare()[..read.len()].copy_from_slice(read);
+/// assert_eq!(reframer.commit(read.len())?, "a");
+///
+/// let read = reads.next().unwrap();
+/// reframer.spare()[..read.len()].copy_from_slice(read);
+/// assert_eq!(reframer.commit(read.len())?, "🎄b");
+/// reframer.finish()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+/// If [Utf8Reframer::commit] hits invalid input after some valid characters, it returns just
+/// the valid characters. The next call returns the error, and bytes after the error are kept for
+/// the call after that. Call `commit(0)` to keep decoding them without reading more.
+#[derive(Clone, Debug)]
+pub struct Utf8Reframer<const N: usize> {
+    buf: [u8; N],
+    // Where the kept bytes begin in `buf`
+    start: usize,
+    // Bytes of a partial sequence, already pushed into the parser
+    carry: usize,
+    // Bytes after the carry that haven't been pushed into the parser yet
+    unprocessed: usize,
+    parser: Utf8Parser,
+}
+
+impl<const N: usize> Utf8Reframer<N> {
+    /// Construct a new, empty reframer
+    pub const fn new() -> Self {
+        assert!(N >= 4, "Utf8Reframer needs room for at least 4 bytes");
+        Self {
+            buf: [0; N],
+            start: 0,
+            carry: 0,
+            unprocessed: 0,
+            parser: Utf8Parser::new(),
+        }
+    }
+
+    /// The part of the buffer to read new bytes into
+    pub fn spare(&mut self) -> &mut [u8] {
+        self.compact();
+        &mut self.buf[self.carry + self.unprocessed..]
+    }
+
+    /// Decode the first `filled` bytes of [Utf8Reframer::spare], along with any bytes kept from
+    /// before
+    ///
+    /// Returns every complete character. A trailing partial sequence is kept for next time.
+    ///
+    /// # Panics
+    /// Panics if `filled` is larger than [Utf8Reframer::spare].
+    pub fn commit(&mut self, filled: usize) -> Result<&str, Utf8ParserError> {
+        self.compact();
+        let end = self.carry + self.unprocessed + filled;
+        assert!(end <= N, "filled more bytes than the spare buffer holds");
+
+        // Index just past the last complete character
+        let mut boundary = 0;
+        for i in self.carry..end {
+            match self.parser.push(self.buf[i]) {
+                Ok(_) => {
+                    if self.parser.is_idle() {
+                        boundary = i + 1;
+                    }
+                }
+                Err(err) => {
+                    if boundary == 0 {
+                        // Drop everything up to and including the bad byte
+                        self.start = i + 1;
+                        self.carry = 0;
+                        self.unprocessed = end - self.start;
+                        return Err(err);
+                    }
+                    // Give back the valid characters first. The parser reset, so the rest
+                    // gets pushed again (and errors again) next time.
+                    self.start = boundary;
+                    self.carry = 0;
+                    self.unprocessed = end - boundary;
+                    return Ok(Self::as_str(&self.buf[..boundary]));
+                }
+            }
+        }
+
+        self.start = boundary;
+        self.carry = end - boundary;
+        self.unprocessed = 0;
+        Ok(Self::as_str(&self.buf[..boundary]))
+    }
+
+    /// Signal the end of input
+    ///
+    /// Errors with [Utf8ParserError::UnexpectedEndOfInput] if a partial sequence is left over.
+    /// Any kept bytes are discarded either way.
+    pub fn finish(&mut self) -> Result<(), Utf8ParserError> {
+        self.start = 0;
+        self.carry = 0;
+        self.unprocessed = 0;
+        self.parser.finish()
+    }
+
+    // Move kept bytes to the front of the buffer
+    fn compact(&mut self) {
+        let len = self.carry + self.unprocessed;
+        self.buf.copy_within(self.start..self.start + len, 0);
+        self.start = 0;
+    }
+
+    fn as_str(bytes: &[u8]) -> &str {
+        core::str::from_utf8(bytes).expect("bytes were validated by the parser")
+    }
+}
+
+impl<const N: usize> Default for Utf8Reframer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Feed `bytes` to a reframer, split up into reads at `splits`
+    fn reframe<const N: usize>(bytes: &[u8], splits: &[usize]) -> Result<String, Utf8ParserError> {
+        let mut reframer = Utf8Reframer::<N>::new();
+        let mut decoded = String::new();
+        let mut last = 0;
+        for &split in splits.iter().chain([bytes.len()].iter()) {
+            let read = &bytes[last..split];
+            reframer.spare()[..read.len()].copy_from_slice(read);
+            decoded.push_str(reframer.commit(read.len())?);
+            last = split;
+        }
+        reframer.finish()?;
+        Ok(decoded)
+    }
+
+    #[test]
+    fn every_boundary() {
+        let original = "aé€🎄b🦊";
+        let bytes = original.as_bytes();
+        for first in 0..=bytes.len() {
+            for second in first..=bytes.len() {
+                assert_eq!(
+                    reframe::<32>(bytes, &[first, second]),
+                    Ok(original.into()),
+                    "splits {first} {second}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn byte_at_a_time() {
+        let original = "Thé quick brown 🦊";
+        let bytes = original.as_bytes();
+        let splits: Vec<usize> = (1..bytes.len()).collect();
+        assert_eq!(reframe::<4>(bytes, &splits), Ok(original.into()));
+    }
+
+    #[test]
+    fn truncated() {
+        assert_eq!(
+            reframe::<4>(&[b'a', 0xf0, 0x9f], &[]),
+            Err(Utf8ParserError::UnexpectedEndOfInput)
+        );
+    }
+
+    #[test]
+    fn invalid_byte() {
+        let mut reframer = Utf8Reframer::<8>::new();
+        let read = [b'a', b'b', 0xff, b'c', 0xc3];
+        reframer.spare()[..read.len()].copy_from_slice(&read);
+        assert_eq!(reframer.commit(read.len()), Ok("ab"));
+        assert_eq!(reframer.commit(0), Err(Utf8ParserError::InvalidByte(0xff)));
+        assert_eq!(reframer.spare().len(), 6);
+        reframer.spare()[0] = 0xa9;
+        assert_eq!(reframer.commit(1), Ok("cé"));
+        assert_eq!(reframer.spare().len(), 8);
+        assert_eq!(reframer.finish(), Ok(()));
+    }
+}
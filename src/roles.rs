@@ -0,0 +1,135 @@
+use crate::{Utf8ByteType, Utf8Parser};
+
+/// The part a byte plays in its surrounding UTF-8, as classified by [byte_roles]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ByteRole {
+    /// A one-byte character
+    Ascii,
+    /// The first byte of a character of this many bytes
+    StartOf(usize),
+    /// A continuation of the character started by an earlier byte
+    Continuation,
+    /// A byte that isn't part of any valid character
+    Invalid,
+}
+
+/// Classify each byte of `bytes` by its role, writing the roles to `out`
+///
+/// Unlike [Utf8ByteType::of], this follows the bytes around each one, so a continuation byte
+/// where a start byte was expected is [ByteRole::Invalid], as is every byte of a malformed
+/// sequence. Sequences are split up the same way as in [Lenient Mode](Utf8Parser#lenient-mode),
+/// so each run of invalid bytes corresponds to one U+FFFD.
+///
+/// A sequence still incomplete at the end of `bytes` isn't marked invalid, since more bytes may
+/// complete it. Only the first `out.len()` bytes are classified if `out` is shorter than
+/// `bytes`.
+///
+/// # Example
+/// ```
+/// use utf8_parser::{byte_roles, ByteRole};
+///
+/// let mut roles = [ByteRole::Invalid; 6];
+/// byte_roles(&[b'a', 0xc3, 0xa9, 0xa9, 0xe2, 0x82], &mut roles);
+/// assert_eq!(
+///     roles,
+///     [
+///         ByteRole::Ascii,
+///         ByteRole::StartOf(2),
+///         ByteRole::Continuation,
+///         ByteRole::Invalid,
+///         ByteRole::StartOf(3),
+///         ByteRole::Continuation,
+///     ]
+/// );
+/// ```
+pub fn byte_roles(bytes: &[u8], out: &mut [ByteRole]) {
+    let mut parser = Utf8Parser::new();
+    // Index of the first byte of the current sequence
+    let mut start = 0;
+
+    for (index, byte) in bytes.iter().copied().enumerate().take(out.len()) {
+        if parser.in_progress() && !parser.accepts(byte) {
+            // The bytes so far are a maximal subpart, so this byte starts over
+            out[start..index].fill(ByteRole::Invalid);
+            parser.reset();
+        }
+        if parser.is_idle() {
+            start = index;
+        }
+
+        out[index] = if !parser.accepts(byte) {
+            ByteRole::Invalid
+        } else {
+            match Utf8ByteType::of(byte).map(Utf8ByteType::sequence_length) {
+                Ok(Some(1)) => ByteRole::Ascii,
+                Ok(Some(len)) => ByteRole::StartOf(len),
+                Ok(None) => ByteRole::Continuation,
+                Err(_) => ByteRole::Invalid,
+            }
+        };
+        if out[index] != ByteRole::Invalid {
+            // `accepts` has already ruled out every way this can fail
+            let _ = parser.push(byte);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn invalid_runs_match_lossy() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..10_000 {
+            let bytes: Vec<u8> = (0..rng.gen_range(0..12))
+                .map(|_| {
+                    [b'a', 0x80, 0xbf, 0xc3, 0xe0, 0xed, 0xa0, 0xf0, 0x9f, 0xff]
+                        [rng.gen_range(0..10)]
+                })
+                .collect();
+            let mut roles = vec![ByteRole::Ascii; bytes.len()];
+            byte_roles(&bytes, &mut roles);
+
+            // Rebuild the lossy decoding from the roles alone
+            let mut rebuilt = String::new();
+            let mut index = 0;
+            while index < bytes.len() {
+                let len = match roles[index] {
+                    ByteRole::Ascii => 1,
+                    ByteRole::StartOf(len) => len,
+                    ByteRole::Continuation => panic!("{bytes:x?} {roles:?}"),
+                    ByteRole::Invalid => {
+                        let run = roles[index..]
+                            .iter()
+                            .take_while(|role| **role == ByteRole::Invalid)
+                            .count();
+                        rebuilt.push_str(&String::from_utf8_lossy(&bytes[index..index + run]));
+                        index += run;
+                        continue;
+                    }
+                };
+                let end = (index + len).min(bytes.len());
+                assert!(roles[index + 1..end]
+                    .iter()
+                    .all(|role| *role == ByteRole::Continuation));
+                rebuilt.push_str(&String::from_utf8_lossy(&bytes[index..end]));
+                index = end;
+            }
+            assert_eq!(
+                rebuilt,
+                String::from_utf8_lossy(&bytes),
+                "{bytes:x?} {roles:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn short_output() {
+        let mut roles = [ByteRole::Invalid; 2];
+        byte_roles("é🦊".as_bytes(), &mut roles);
+        assert_eq!(roles, [ByteRole::StartOf(2), ByteRole::Continuation]);
+    }
+}
@@ -0,0 +1,682 @@
+use crate::{Config, Core, Utf8ByteType, Utf8Error, Utf8Parser, Utf8ParserError};
+
+/// Classify each byte of a slice with [Utf8ByteType::of]
+///
+/// Invalid bytes yield an error without ending iteration.
+///
+/// # Example
+/// ```
+/// use utf8_parser::{classify, Utf8ByteType, Utf8ParserError};
+///
+/// let types: Vec<_> = classify(&[b'a', 0xc3, 0xa9, 0xff]).collect();
+/// assert_eq!(
+///     types,
+///     [
+///         Ok(Utf8ByteType::Single),
+///         Ok(Utf8ByteType::Double),
+///         Ok(Utf8ByteType::Continuation),
+///         Err(Utf8ParserError::InvalidByte(0xff)),
+///     ]
+/// );
+/// ```
+pub fn classify(bytes: &[u8]) -> impl Iterator<Item = Result<Utf8ByteType, Utf8ParserError>> + '_ {
+    bytes.iter().map(|byte| Utf8ByteType::of(*byte))
+}
+
+/// Check that a complete byte slice is valid UTF-8
+///
+/// Truncated input is an error, as with [Utf8Parser::finish].
+///
+/// # Example
+/// ```
+/// use utf8_parser::{validate, Utf8ParserError};
+///
+/// assert_eq!(validate("h🎄".as_bytes()), Ok(()));
+/// assert_eq!(validate(&[0xf0, 0x9f]), Err(Utf8ParserError::UnexpectedEndOfInput));
+/// ```
+pub fn validate(bytes: &[u8]) -> Result<(), Utf8ParserError> {
+    let mut parser = Utf8Parser::new();
+    for byte in bytes {
+        parser.push(*byte)?;
+    }
+    parser.finish()
+}
+
+/// Returns true if a complete byte slice is valid UTF-8
+///
+/// See [validate].
+pub fn is_valid_utf8(bytes: &[u8]) -> bool {
+    validate(bytes).is_ok()
+}
+
+/// Returns true if a complete byte slice is valid UTF-8, in a `const` context
+///
+/// This is [is_valid_utf8] as a `const fn`, so hand-written byte arrays can be checked at
+/// compile time.
+///
+/// # Example
+/// ```
+/// use utf8_parser::validate_const;
+///
+/// const GREETING: &[u8] = &[b'h', 0xc3, 0xa9, b'!'];
+/// const { assert!(validate_const(GREETING)) };
+/// assert!(!validate_const(&[0xc0, 0x80]));
+/// ```
+pub const fn validate_const(bytes: &[u8]) -> bool {
+    let config = Config::new();
+    let mut core = Core::new();
+    let mut index = 0;
+    while index < bytes.len() {
+        core = match core.step(&config, bytes[index]) {
+            Ok((next, _)) => next,
+            Err(_) => return false,
+        };
+        index += 1;
+    }
+    core.is_idle()
+}
+
+/// Length of the longest prefix of `bytes` that is complete, valid UTF-8
+///
+/// This is equivalent to [core::str::Utf8Error::valid_up_to]. A partial sequence at the end of
+/// the slice is not counted.
+///
+/// # Example
+/// ```
+/// use utf8_parser::valid_prefix_len;
+///
+/// assert_eq!(valid_prefix_len(b"abc"), 3);
+/// assert_eq!(valid_prefix_len(&[b'a', 0xff, b'b']), 1);
+/// assert_eq!(valid_prefix_len(&[b'a', 0xf0, 0x9f]), 1);
+/// ```
+pub fn valid_prefix_len(bytes: &[u8]) -> usize {
+    let mut parser = Utf8Parser::new();
+    let mut valid = 0;
+    for (offset, byte) in bytes.iter().enumerate() {
+        match parser.push(*byte) {
+            Ok(Some(_)) => valid = offset + 1,
+            Ok(None) => {}
+            Err(_) => break,
+        }
+    }
+    valid
+}
+
+/// Index of the first byte in `bytes` that can start a character, or `bytes.len()` if there
+/// isn't one
+///
+/// This skips continuation bytes and invalid bytes, which is the usual way to recover from an
+/// error in a damaged stream: reset the parser, then carry on from the next character
+/// boundary.
+///
+/// # Example
+/// ```
+/// use utf8_parser::{resync, Utf8Parser};
+///
+/// let bytes = [0xa9, 0x80, 0xff, b'a', 0xc3, 0xa9];
+/// let mut parser = Utf8Parser::new();
+/// assert!(parser.push(bytes[0]).is_err());
+/// let skip = 1 + resync(&bytes[1..]);
+/// assert_eq!(skip, 3);
+///
+/// let decoded: Result<String, _> = parser.feed(&bytes[skip..]).collect();
+/// assert_eq!(decoded.unwrap(), "aé");
+/// ```
+pub fn resync(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .position(|byte| Utf8ByteType::of(*byte).is_ok_and(Utf8ByteType::is_start))
+        .unwrap_or(bytes.len())
+}
+
+/// Split `bytes` before a trailing partial character
+///
+/// The second slice is the longest suffix that's an incomplete, but so far valid, UTF-8
+/// sequence: 0 to 3 bytes to carry over to the next chunk of a stream. The first slice is
+/// everything before it.
+///
+/// This doesn't validate the first slice. Invalid bytes at the end of `bytes` aren't treated
+/// as a partial character, so they stay in the first slice, where decoding it will report them.
+///
+/// # Example
+/// ```
+/// use utf8_parser::split_at_last_complete;
+///
+/// // The first two bytes of '🎄'
+/// let bytes = [b'a', 0xf0, 0x9f];
+/// assert_eq!(split_at_last_complete(&bytes), (&bytes[..1], &bytes[1..]));
+///
+/// // 0xe0 0x80 can never start a valid character
+/// let bytes = [b'a', 0xe0, 0x80];
+/// assert_eq!(split_at_last_complete(&bytes), (&bytes[..], &[][..]));
+/// ```
+pub fn split_at_last_complete(bytes: &[u8]) -> (&[u8], &[u8]) {
+    // A partial character is at most 3 bytes
+    for tail_len in 1..=bytes.len().min(3) {
+        let start = bytes.len() - tail_len;
+        match Utf8ByteType::of(bytes[start]).map(Utf8ByteType::sequence_length) {
+            // Keep looking for the start byte
+            Ok(None) => continue,
+            Ok(Some(len)) if len > tail_len && is_partial(&bytes[start..]) => {
+                return bytes.split_at(start);
+            }
+            _ => break,
+        }
+    }
+    (bytes, &[])
+}
+
+// Whether `bytes` is a valid prefix of a multi-byte sequence
+fn is_partial(bytes: &[u8]) -> bool {
+    let mut parser = Utf8Parser::new();
+    bytes
+        .iter()
+        .all(|byte| parser.accepts(*byte) && parser.push(*byte) == Ok(None))
+}
+
+/// Convert a byte slice to a `&str`, with the same error reporting as [core::str::from_utf8]
+///
+/// Unlike [validate], the error reports how many invalid bytes to skip, following the same
+/// "substitution of maximal subparts" practice as [Lenient Mode](Utf8Parser#lenient-mode).
+///
+/// # Example
+/// ```
+/// use utf8_parser::from_utf8;
+///
+/// assert_eq!(from_utf8(b"abc"), Ok("abc"));
+///
+/// let err = from_utf8(&[b'a', 0xe0, 0x80, b'b']).unwrap_err();
+/// assert_eq!(err.valid_up_to(), 1);
+/// assert_eq!(err.error_len(), Some(1));
+///
+/// let err = from_utf8(&[b'a', 0xf0, 0x9f]).unwrap_err();
+/// assert_eq!(err.valid_up_to(), 1);
+/// assert_eq!(err.error_len(), None);
+/// ```
+pub fn from_utf8(bytes: &[u8]) -> Result<&str, Utf8Error> {
+    let mut parser = Utf8Parser::new();
+    let mut valid = 0;
+
+    for (offset, byte) in bytes.iter().enumerate() {
+        if !parser.accepts(*byte) {
+            let error_len = if parser.is_idle() { 1 } else { offset - valid };
+            return Err(Utf8Error::new(valid, Some(error_len as u8)));
+        }
+        // `accepts` has already ruled out every way this can fail
+        if let Ok(Some(_)) = parser.push(*byte) {
+            valid = offset + 1;
+        }
+    }
+
+    if parser.in_progress() {
+        return Err(Utf8Error::new(valid, None));
+    }
+
+    // Already validated, but we can't skip the check without `unsafe`
+    Ok(core::str::from_utf8(bytes)?)
+}
+
+/// Convert a complete byte slice to a `&str`, with the same errors as [validate]
+///
+/// This borrows from `bytes` rather than copying. Use [from_utf8] instead for
+/// [core::str::from_utf8]-style error reporting.
+///
+/// # Example
+/// ```
+/// use utf8_parser::{decode_str, Utf8ParserError};
+///
+/// assert_eq!(decode_str("h🎄".as_bytes()), Ok("h🎄"));
+/// assert_eq!(decode_str(&[b'a', 0xff]), Err(Utf8ParserError::InvalidByte(0xff)));
+/// assert_eq!(decode_str(&[0xf0, 0x9f]), Err(Utf8ParserError::UnexpectedEndOfInput));
+/// ```
+pub fn decode_str(bytes: &[u8]) -> Result<&str, Utf8ParserError> {
+    validate(bytes)?;
+    // Already validated, but we can't skip the check without `unsafe`
+    Ok(core::str::from_utf8(bytes).expect("bytes were validated by the parser"))
+}
+
+/// Decode the first character of `bytes`, returning it with the number of bytes it took
+///
+/// This is the single-character counterpart to [decode_str]. Errors with
+/// [Utf8ParserError::UnexpectedEndOfInput] if `bytes` is empty or ends partway through the
+/// character.
+///
+/// # Example
+/// ```
+/// use utf8_parser::{next_char, Utf8ParserError};
+///
+/// assert_eq!(next_char("🎄 tree".as_bytes()), Ok(('🎄', 4)));
+/// assert_eq!(next_char(&[0xc3, b'a']), Err(Utf8ParserError::UnexpectedStartByte(b'a')));
+/// assert_eq!(next_char(&[0xc3]), Err(Utf8ParserError::UnexpectedEndOfInput));
+/// assert_eq!(next_char(&[]), Err(Utf8ParserError::UnexpectedEndOfInput));
+/// ```
+pub fn next_char(bytes: &[u8]) -> Result<(char, usize), Utf8ParserError> {
+    let mut parser = Utf8Parser::new();
+    for (offset, byte) in bytes.iter().enumerate() {
+        if let Some(c) = parser.push(*byte)? {
+            return Ok((c, offset + 1));
+        }
+    }
+    Err(Utf8ParserError::UnexpectedEndOfInput)
+}
+
+/// Decode a complete byte slice into a caller-provided buffer, returning the number of characters
+/// written
+///
+/// Errors with [Utf8ParserError::OutputBufferFull] if `out` can't hold every decoded character,
+/// and with [Utf8ParserError::UnexpectedEndOfInput] if `input` ends mid-sequence.
+///
+/// # Example
+/// ```
+/// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+/// use utf8_parser::decode_slice;
+///
+/// let mut out = ['\0'; 8];
+/// let len = decode_slice("h🎄".as_bytes(), &mut out)?;
+/// assert_eq!(&out[..len], ['h', '🎄']);
+/// # Ok(())
+/// # }
+/// ```
+pub fn decode_slice(input: &[u8], out: &mut [char]) -> Result<usize, Utf8ParserError> {
+    let mut parser = Utf8Parser::new();
+    let mut written = 0;
+
+    for byte in input {
+        if let Some(c) = parser.push(*byte)? {
+            let slot = out
+                .get_mut(written)
+                .ok_or(Utf8ParserError::OutputBufferFull)?;
+            *slot = c;
+            written += 1;
+        }
+    }
+
+    parser.finish()?;
+    Ok(written)
+}
+
+/// Decode a complete byte slice into a caller-provided buffer of UTF-32 code points, returning
+/// the number of code points written
+///
+/// This is [decode_slice] for consumers that want `u32` scalars rather than [char]s, with the
+/// same errors.
+///
+/// # Example
+/// ```
+/// # fn main() -> Result<(), utf8_parser::Utf8ParserError> {
+/// use utf8_parser::decode_utf32;
+///
+/// let mut out = [0; 8];
+/// let len = decode_utf32("h🎄".as_bytes(), &mut out)?;
+/// assert_eq!(&out[..len], [0x68, 0x1f384]);
+/// # Ok(())
+/// # }
+/// ```
+pub fn decode_utf32(input: &[u8], out: &mut [u32]) -> Result<usize, Utf8ParserError> {
+    let mut parser = Utf8Parser::new();
+    let mut written = 0;
+
+    for byte in input {
+        if let Some(code_point) = parser.push_scalar(*byte)? {
+            let slot = out
+                .get_mut(written)
+                .ok_or(Utf8ParserError::OutputBufferFull)?;
+            *slot = code_point;
+            written += 1;
+        }
+    }
+
+    parser.finish()?;
+    Ok(written)
+}
+
+/// Decode a complete byte slice into a [String](alloc::string::String)
+///
+/// On error, also returns the index of the byte that caused it. If the input ends partway
+/// through a multi-byte character, that index is the length of the input.
+///
+/// # Example
+/// ```
+/// use utf8_parser::{decode_with_offset, Utf8ParserError};
+///
+/// assert_eq!(decode_with_offset(b"abc").unwrap(), "abc");
+/// assert_eq!(
+///     decode_with_offset(&[b'a', b'b', 0xff]),
+///     Err((Utf8ParserError::InvalidByte(0xff), 2))
+/// );
+/// ```
+#[cfg(feature = "alloc")]
+pub fn decode_with_offset(input: &[u8]) -> Result<alloc::string::String, (Utf8ParserError, usize)> {
+    let mut parser = Utf8Parser::new();
+    let mut decoded = alloc::string::String::new();
+
+    for (offset, byte) in input.iter().enumerate() {
+        if let Some(c) = parser.push(*byte).map_err(|err| (err, offset))? {
+            decoded.push(c);
+        }
+    }
+
+    parser.finish().map_err(|err| (err, input.len()))?;
+    Ok(decoded)
+}
+
+/// Decode a complete byte slice, replacing malformed input with U+FFFD
+///
+/// Valid input is borrowed rather than copied. Replacement follows
+/// [Lenient Mode](Utf8Parser#lenient-mode), like [`String::from_utf8_lossy`].
+///
+/// [`String::from_utf8_lossy`]: https://doc.rust-lang.org/std/string/struct.String.html#method.from_utf8_lossy
+///
+/// # Example
+/// ```
+/// use std::borrow::Cow;
+/// use utf8_parser::decode_cow;
+///
+/// assert!(matches!(decode_cow("h🎄".as_bytes()), Cow::Borrowed("h🎄")));
+/// assert_eq!(decode_cow(&[b'a', 0xff, 0xf0, 0x9f]), "a\u{fffd}\u{fffd}");
+/// ```
+#[cfg(feature = "alloc")]
+pub fn decode_cow(bytes: &[u8]) -> alloc::borrow::Cow<'_, str> {
+    use alloc::borrow::Cow;
+
+    match core::str::from_utf8(bytes) {
+        Ok(valid) => Cow::Borrowed(valid),
+        Err(_) => {
+            let mut parser = Utf8Parser::new_lenient();
+            // Lenient mode never errors
+            Cow::Owned(parser.decode_all(bytes).unwrap_or_default())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn classify_continues_past_invalid_bytes() {
+        let types: Vec<_> = classify(&[0xff, 0xf8, b'a', 0xf0]).collect();
+        assert_eq!(
+            types,
+            [
+                Err(Utf8ParserError::InvalidByte(0xff)),
+                Err(Utf8ParserError::InvalidByte(0xf8)),
+                Ok(Utf8ByteType::Single),
+                Ok(Utf8ByteType::Quadruple),
+            ]
+        );
+    }
+
+    // Random strings of mostly multi-byte characters, with occasional corruption and truncation
+    fn fuzz_corpus() -> impl Iterator<Item = Vec<u8>> {
+        let mut rng = rand::thread_rng();
+        (0..100_000).map(move |_| {
+            let len = rng.gen_range(0..8);
+            let mut bytes: Vec<u8> = (0..len)
+                .flat_map(|_| {
+                    let c: char = rng.gen();
+                    c.to_string().into_bytes()
+                })
+                .collect();
+            if !bytes.is_empty() && rng.gen_bool(0.5) {
+                let index = rng.gen_range(0..bytes.len());
+                bytes[index] = rng.gen();
+            }
+            if !bytes.is_empty() && rng.gen_bool(0.2) {
+                bytes.pop();
+            }
+            bytes
+        })
+    }
+
+    // Uniformly random bytes, which are mostly invalid
+    fn random_bytes() -> impl Iterator<Item = Vec<u8>> {
+        let mut rng = rand::thread_rng();
+        (0..100_000).map(move |_| {
+            let len = rng.gen_range(0..6);
+            (0..len).map(|_| rng.gen()).collect()
+        })
+    }
+
+    #[test]
+    fn validate_agrees_with_core() {
+        for bytes in fuzz_corpus().chain(random_bytes()) {
+            assert_eq!(
+                is_valid_utf8(&bytes),
+                core::str::from_utf8(&bytes).is_ok(),
+                "{bytes:x?}"
+            );
+        }
+    }
+
+    #[test]
+    fn validate_const_agrees_with_core() {
+        const { assert!(validate_const("h🎄".as_bytes())) };
+        const { assert!(!validate_const(&[0xed, 0xa0, 0x80])) };
+        for bytes in fuzz_corpus().chain(random_bytes()) {
+            assert_eq!(
+                validate_const(&bytes),
+                core::str::from_utf8(&bytes).is_ok(),
+                "{bytes:x?}"
+            );
+        }
+    }
+
+    #[test]
+    fn split_at_last_complete_agrees_with_core() {
+        for bytes in fuzz_corpus().chain(random_bytes()) {
+            let (head, tail) = split_at_last_complete(&bytes);
+            assert_eq!([head, tail].concat(), bytes);
+            match core::str::from_utf8(&bytes) {
+                Ok(_) => assert!(tail.is_empty(), "{bytes:x?}"),
+                Err(err) if err.error_len().is_none() => {
+                    assert_eq!(tail, &bytes[err.valid_up_to()..], "{bytes:x?}");
+                }
+                // The tail is still a partial character, even after an earlier error
+                Err(_) => match core::str::from_utf8(tail) {
+                    Ok(valid) => assert!(valid.is_empty(), "{bytes:x?}"),
+                    Err(err) => {
+                        assert_eq!(
+                            (err.valid_up_to(), err.error_len()),
+                            (0, None),
+                            "{bytes:x?}"
+                        );
+                    }
+                },
+            }
+        }
+    }
+
+    #[test]
+    fn next_char_agrees_with_core() {
+        for bytes in fuzz_corpus().chain(random_bytes()) {
+            let expected = match core::str::from_utf8(&bytes) {
+                Ok(s) => s.chars().next(),
+                Err(err) => core::str::from_utf8(&bytes[..err.valid_up_to()])
+                    .unwrap()
+                    .chars()
+                    .next(),
+            };
+            let decoded = next_char(&bytes).ok();
+            assert_eq!(decoded.map(|(c, _)| c), expected, "{bytes:x?}");
+            if let Some((c, len)) = decoded {
+                assert_eq!(len, c.len_utf8());
+            }
+        }
+    }
+
+    #[test]
+    fn resync_skips_continuation_bytes() {
+        assert_eq!(resync(&[]), 0);
+        assert_eq!(resync(b"abc"), 0);
+        assert_eq!(resync(&[0x80; 16]), 16);
+        assert_eq!(resync(&[0x80, 0xbf, 0xa0, 0xf0, 0x9f]), 3);
+        assert_eq!(resync(&[0xf8, 0xff, 0x80, 0xc3]), 3);
+
+        // Lands on the start of every character in a damaged stream
+        let original = "Thé quick brown 🦊 jamped over the lazy 🐕".as_bytes();
+        let starts: Vec<_> = core::str::from_utf8(original)
+            .unwrap()
+            .char_indices()
+            .map(|(offset, _)| offset)
+            .collect();
+        for offset in 0..original.len() {
+            let next = offset + resync(&original[offset..]);
+            let expected = starts.iter().copied().find(|start| *start >= offset);
+            assert_eq!(next, expected.unwrap_or(original.len()));
+        }
+    }
+
+    #[test]
+    fn decode_str_agrees_with_core() {
+        for bytes in fuzz_corpus() {
+            assert_eq!(
+                decode_str(&bytes).ok(),
+                core::str::from_utf8(&bytes).ok(),
+                "{bytes:x?}"
+            );
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn decode_cow_matches_from_utf8_lossy() {
+        use alloc::borrow::Cow;
+
+        for bytes in fuzz_corpus() {
+            let ours = decode_cow(&bytes);
+            let theirs = String::from_utf8_lossy(&bytes);
+            assert_eq!(ours, theirs, "{bytes:x?}");
+            assert_eq!(
+                matches!(ours, Cow::Borrowed(_)),
+                matches!(theirs, Cow::Borrowed(_)),
+                "{bytes:x?}"
+            );
+        }
+    }
+
+    #[test]
+    fn decode_agrees_with_core() {
+        for bytes in fuzz_corpus().chain(random_bytes()) {
+            let decoded: Result<String, _> = crate::decode_utf8(bytes.iter().copied()).collect();
+            assert_eq!(
+                decoded.ok().as_deref(),
+                core::str::from_utf8(&bytes).ok(),
+                "{bytes:x?}"
+            );
+        }
+    }
+
+    #[test]
+    fn valid_prefix_len_agrees_with_core() {
+        for bytes in fuzz_corpus() {
+            let expected = match core::str::from_utf8(&bytes) {
+                Ok(_) => bytes.len(),
+                Err(err) => err.valid_up_to(),
+            };
+            assert_eq!(valid_prefix_len(&bytes), expected, "{bytes:x?}");
+        }
+    }
+
+    #[test]
+    fn from_utf8_agrees_with_core() {
+        for bytes in fuzz_corpus() {
+            assert_eq!(
+                from_utf8(&bytes),
+                core::str::from_utf8(&bytes).map_err(Utf8Error::from),
+                "{bytes:x?}"
+            );
+        }
+
+        for bytes in [
+            &[0xed, 0xa0, 0x80][..],
+            &[0xf4, 0x90, 0x80, 0x80],
+            &[0xf0, 0x9f, 0x8e, b'a'],
+            &[0xc0, 0x80],
+            &[0xed, 0xa0],
+        ] {
+            assert_eq!(
+                from_utf8(bytes),
+                core::str::from_utf8(bytes).map_err(Utf8Error::from),
+                "{bytes:x?}"
+            );
+        }
+    }
+
+    #[test]
+    fn decode_into_buffer() -> Result<(), Utf8ParserError> {
+        let original = "Thé quick brown 🦊";
+        let mut out = ['\0'; 32];
+        let len = decode_slice(original.as_bytes(), &mut out)?;
+        assert_eq!(out[..len].iter().collect::<String>(), original);
+        Ok(())
+    }
+
+    #[test]
+    fn decode_exact_fit() -> Result<(), Utf8ParserError> {
+        let mut out = ['\0'; 2];
+        assert_eq!(decode_slice("é🦊".as_bytes(), &mut out)?, 2);
+        assert_eq!(out, ['é', '🦊']);
+        assert_eq!(decode_slice(&[], &mut [])?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn error_when_buffer_too_small() {
+        let mut out = ['\0'; 2];
+        assert_eq!(
+            decode_slice(b"abc", &mut out),
+            Err(Utf8ParserError::OutputBufferFull)
+        );
+        assert_eq!(out, ['a', 'b']);
+    }
+
+    #[test]
+    fn decode_utf32_matches_decode_slice() {
+        for bytes in fuzz_corpus() {
+            let mut chars = ['\0'; 32];
+            let mut code_points = [0; 32];
+            let expected = decode_slice(&bytes, &mut chars)
+                .map(|len| chars[..len].iter().map(|c| *c as u32).collect::<Vec<_>>());
+            let decoded =
+                decode_utf32(&bytes, &mut code_points).map(|len| code_points[..len].to_vec());
+            assert_eq!(decoded, expected, "{bytes:x?}");
+        }
+
+        let mut out = [0; 2];
+        assert_eq!(
+            decode_utf32(b"abc", &mut out),
+            Err(Utf8ParserError::OutputBufferFull)
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn decode_reports_offset() {
+        let original = "Thé 🦊".as_bytes();
+        assert_eq!(decode_with_offset(original).unwrap(), "Thé 🦊");
+
+        // Offset points at the offending byte, not the start of its sequence
+        let mut bad = original.to_vec();
+        bad[6] = b'x';
+        assert_eq!(
+            decode_with_offset(&bad),
+            Err((Utf8ParserError::UnexpectedStartByte(b'x'), 6))
+        );
+
+        assert_eq!(
+            decode_with_offset(&original[..original.len() - 1]),
+            Err((Utf8ParserError::UnexpectedEndOfInput, original.len() - 1))
+        );
+    }
+
+    #[test]
+    fn error_on_truncated_input() {
+        let mut out = ['\0'; 4];
+        assert_eq!(
+            decode_slice(&[b'a', 0xf0, 0x9f, 0x8e], &mut out),
+            Err(Utf8ParserError::UnexpectedEndOfInput)
+        );
+    }
+}
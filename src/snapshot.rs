@@ -0,0 +1,259 @@
+use crate::pending::{PendingChars, Span};
+use crate::{
+    partial_state, Config, Core, State, Utf8Parser, Utf8ParserState, FIRST_SURROGATE,
+    LAST_HIGH_SURROGATE,
+};
+
+// The serialized form of a `Utf8ParserState`
+//
+// This is kept apart from the state itself so the parser's internals can change without
+// breaking saved snapshots. Missing fields take their values from a fresh parser, so a field
+// added here still accepts snapshots from before it existed. Every snapshot is checked on the
+// way in, since a crafted one could otherwise put the parser in a state it can't handle.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "Utf8ParserState", default)]
+pub(crate) struct SerializedState {
+    state: State,
+    config: Config,
+    pending: PendingChars,
+    bytes_consumed: u64,
+    high_surrogate: Option<u32>,
+    seen_first_char: bool,
+    sequence_len: u8,
+    held_cr: Option<Span>,
+    sequence_start: u64,
+    char_start: u64,
+}
+
+impl Default for SerializedState {
+    fn default() -> Self {
+        Utf8Parser::new().save_state().into()
+    }
+}
+
+impl From<Utf8ParserState> for SerializedState {
+    fn from(state: Utf8ParserState) -> Self {
+        Self {
+            state: state.core.state,
+            config: state.config,
+            pending: state.pending,
+            bytes_consumed: state.bytes_consumed,
+            high_surrogate: state.core.high_surrogate,
+            seen_first_char: state.seen_first_char,
+            sequence_len: state.core.sequence_len,
+            held_cr: state.held_cr,
+            sequence_start: state.sequence_start,
+            char_start: state.char_start,
+        }
+    }
+}
+
+impl TryFrom<SerializedState> for Utf8ParserState {
+    type Error = &'static str;
+
+    fn try_from(serialized: SerializedState) -> Result<Self, Self::Error> {
+        let SerializedState {
+            state,
+            config,
+            pending,
+            bytes_consumed,
+            high_surrogate,
+            seen_first_char,
+            sequence_len,
+            held_cr,
+            sequence_start,
+            char_start,
+        } = serialized;
+
+        if !matches!(config.max_sequence_len, 1..=4) {
+            return Err("sequence length limit must be between 1 and 4");
+        }
+        if config.wtf8 && config.coalesce_crlf {
+            return Err("WTF-8 mode can't be combined with CRLF mode");
+        }
+        let partial = match state {
+            State::Fresh => None,
+            State::OneLeft(value) => Some((1, value)),
+            State::TwoLeft(value) => Some((2, value)),
+            State::ThreeLeft(value) => Some((3, value)),
+        };
+        if let Some((remaining, value)) = partial {
+            if sequence_len > config.max_sequence_len
+                || partial_state(
+                    sequence_len as usize,
+                    remaining,
+                    value,
+                    config.allow_overlong,
+                )
+                .is_none()
+            {
+                return Err("invalid partial sequence");
+            }
+        }
+        if let Some(high) = high_surrogate {
+            if !config.cesu8 || !(FIRST_SURROGATE..=LAST_HIGH_SURROGATE).contains(&high) {
+                return Err("invalid high surrogate");
+            }
+        }
+        if let Some(cr) = held_cr {
+            if !config.coalesce_crlf || cr.len != 1 || !cr.ends_by(bytes_consumed) {
+                return Err("invalid held carriage return");
+            }
+        }
+        if char_start > bytes_consumed {
+            return Err("character starts after the parser's position");
+        }
+        // A lenient parser never rejects bytes partway through a character, so a partial one is
+        // at most a high surrogate and two bytes of its low surrogate
+        let idle = matches!(state, State::Fresh) && high_surrogate.is_none();
+        if config.lenient && !idle && bytes_consumed - char_start > 5 {
+            return Err("partial character is too long");
+        }
+        if !pending.is_valid(bytes_consumed) {
+            return Err("invalid pending characters");
+        }
+
+        Ok(Self {
+            core: Core {
+                state,
+                high_surrogate,
+                sequence_len,
+            },
+            config,
+            pending,
+            bytes_consumed,
+            seen_first_char,
+            held_cr,
+            sequence_start,
+            char_start,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    // Serialize a partway parser, apply `modify` to the JSON, and deserialize it again
+    fn modified(
+        mut parser: Utf8Parser,
+        bytes: &[u8],
+        modify: impl FnOnce(&mut Value),
+    ) -> Result<Utf8ParserState, serde_json::Error> {
+        for byte in bytes {
+            parser.push(*byte).unwrap();
+        }
+        let mut json = serde_json::to_value(parser.save_state()).unwrap();
+        modify(&mut json);
+        serde_json::from_value(json)
+    }
+
+    #[test]
+    fn round_trip() {
+        for (parser, bytes) in [
+            (Utf8Parser::new(), &b"ab\xf0\x9f"[..]),
+            (Utf8Parser::new_lenient(), b"\xe2\x82\xff\xf0"),
+            (Utf8Parser::new_coalesce_crlf(), b"a\r"),
+            (Utf8Parser::new_cesu8(), b"\xed\xa0\xbd\xed"),
+            (
+                Utf8Parser::builder().allow_overlong(true).build(),
+                b"\xe0\x80",
+            ),
+        ] {
+            let mut expected = parser.clone();
+            for byte in bytes {
+                expected.push(*byte).unwrap();
+            }
+            assert_eq!(
+                modified(parser, bytes, |_| {}).unwrap(),
+                expected.save_state()
+            );
+        }
+    }
+
+    #[test]
+    fn missing_fields_default() {
+        let state: Utf8ParserState = serde_json::from_str("{}").unwrap();
+        assert_eq!(state, Utf8Parser::new().save_state());
+
+        let state = modified(Utf8Parser::new_lenient(), b"a\xc3", |json| {
+            json.as_object_mut().unwrap().remove("held_cr");
+            json["config"]
+                .as_object_mut()
+                .unwrap()
+                .remove("reject_controls");
+        })
+        .unwrap();
+        let mut parser = Utf8Parser::from_state(state);
+        assert_eq!(parser.position(), 2);
+        assert_eq!(parser.push(b'b'), Ok(Some('\u{fffd}')));
+    }
+
+    #[test]
+    fn invalid_states() {
+        for (parser, bytes, modify) in [
+            (
+                Utf8Parser::new(),
+                &b"\xc3"[..],
+                &(|json: &mut Value| json["sequence_len"] = json!(0)) as &dyn Fn(&mut Value),
+            ),
+            (Utf8Parser::new(), b"\xc3", &|json| {
+                json["state"] = json!({ "ThreeLeft": 0 })
+            }),
+            (Utf8Parser::new(), b"\xf0\x9f", &|json| {
+                json["state"] = json!({ "TwoLeft": u32::MAX })
+            }),
+            (Utf8Parser::new(), b"\xe0", &|json| {
+                json["state"] = json!({ "OneLeft": 0 })
+            }),
+            (Utf8Parser::new(), b"", &|json| {
+                json["config"]["max_sequence_len"] = json!(0)
+            }),
+            (Utf8Parser::new(), b"", &|json| {
+                json["config"]["max_sequence_len"] = json!(5)
+            }),
+            (
+                Utf8Parser::builder().max_sequence_len(2).build(),
+                b"\xc3",
+                &|json| {
+                    json["state"] = json!({ "TwoLeft": 0b1111 });
+                    json["sequence_len"] = json!(3);
+                },
+            ),
+            (Utf8Parser::new(), b"", &|json| {
+                json["high_surrogate"] = json!(0xd800)
+            }),
+            (Utf8Parser::new_cesu8(), b"", &|json| {
+                json["high_surrogate"] = json!(0)
+            }),
+            (Utf8Parser::new(), b"a", &|json| {
+                json["held_cr"] = json!({ "start": 0, "len": 1 })
+            }),
+            (Utf8Parser::new_coalesce_crlf(), b"\r", &|json| {
+                json["held_cr"] = json!({ "start": u64::MAX, "len": 1 })
+            }),
+            (Utf8Parser::new_coalesce_crlf(), b"\r", &|json| {
+                json["held_cr"] = json!({ "start": 0, "len": 0 })
+            }),
+            (Utf8Parser::new_lenient(), b"\xc3", &|json| {
+                json["bytes_consumed"] = json!(300)
+            }),
+            (Utf8Parser::new_coalesce_crlf(), b"", &|json| {
+                json["config"]["wtf8"] = json!(true)
+            }),
+            (Utf8Parser::new(), b"\xc3", &|json| {
+                json["char_start"] = json!(2)
+            }),
+            (Utf8Parser::new(), b"", &|json| {
+                json["pending"]["len"] = json!(4)
+            }),
+            (Utf8Parser::new(), b"", &|json| {
+                json["pending"]["chars"] = json!(["a", "\0", "\0"])
+            }),
+        ] {
+            let result = modified(parser, bytes, modify);
+            assert!(result.is_err(), "{result:?}");
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use crate::{
+    Utf8ByteType, FIRST_CODE_POINT_FOR_DOUBLE, FIRST_CODE_POINT_FOR_QUADRUPLE,
+    FIRST_CODE_POINT_FOR_TRIPLE,
+};
+
+/// Counts of what a [Utf8Parser](crate::Utf8Parser) has decoded over its lifetime
+///
+/// Characters are counted by the length of their UTF-8 encoding. In
+/// [Lenient Mode](crate::Utf8Parser#lenient-mode), replacement characters are counted as errors
+/// rather than characters.
+///
+/// # Example
+/// ```
+/// use utf8_parser::{Utf8ByteType, Utf8Parser};
+///
+/// let mut parser = Utf8Parser::new();
+/// let _ = parser.feed(&[b'a', b'b', 0xc3, 0xa9, 0xff]).count();
+/// let stats = parser.stats();
+/// assert_eq!(stats.count(Utf8ByteType::Single), 2);
+/// assert_eq!(stats.count(Utf8ByteType::Double), 1);
+/// assert_eq!(stats.errors, 1);
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, Hash)]
+pub struct Utf8Stats {
+    /// Number of one-byte characters
+    pub single: u64,
+    /// Number of two-byte characters
+    pub double: u64,
+    /// Number of three-byte characters
+    pub triple: u64,
+    /// Number of four-byte characters
+    pub quadruple: u64,
+    /// Number of errors
+    pub errors: u64,
+}
+
+impl Utf8Stats {
+    pub(crate) const fn new() -> Self {
+        Self {
+            single: 0,
+            double: 0,
+            triple: 0,
+            quadruple: 0,
+            errors: 0,
+        }
+    }
+
+    /// Number of characters that start with the given type of byte
+    pub const fn count(&self, kind: Utf8ByteType) -> u64 {
+        match kind {
+            Utf8ByteType::Continuation => 0,
+            Utf8ByteType::Single => self.single,
+            Utf8ByteType::Double => self.double,
+            Utf8ByteType::Triple => self.triple,
+            Utf8ByteType::Quadruple => self.quadruple,
+        }
+    }
+
+    /// Total number of characters
+    pub const fn chars(&self) -> u64 {
+        self.single + self.double + self.triple + self.quadruple
+    }
+
+    pub(crate) fn record_char(&mut self, val: u32) {
+        if val < FIRST_CODE_POINT_FOR_DOUBLE {
+            self.single += 1;
+        } else if val < FIRST_CODE_POINT_FOR_TRIPLE {
+            self.double += 1;
+        } else if val < FIRST_CODE_POINT_FOR_QUADRUPLE {
+            self.triple += 1;
+        } else {
+            self.quadruple += 1;
+        }
+    }
+
+    pub(crate) fn record_error(&mut self) {
+        self.errors += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Utf8ByteType, Utf8Parser};
+
+    #[test]
+    fn count_widths() {
+        let mut parser = Utf8Parser::new();
+        let _ = parser.feed("Thé 🦊 €€".as_bytes()).count();
+        let stats = parser.stats();
+        assert_eq!(stats.count(Utf8ByteType::Single), 4);
+        assert_eq!(stats.count(Utf8ByteType::Double), 1);
+        assert_eq!(stats.count(Utf8ByteType::Triple), 2);
+        assert_eq!(stats.count(Utf8ByteType::Quadruple), 1);
+        assert_eq!(stats.count(Utf8ByteType::Continuation), 0);
+        assert_eq!(stats.chars(), 8);
+        assert_eq!(stats.errors, 0);
+    }
+
+    #[test]
+    fn count_errors() {
+        let mut parser = Utf8Parser::new();
+        let _ = parser.feed(&[0xff, 0x80, b'a', 0xf0]).count();
+        assert!(parser.finish().is_err());
+        assert_eq!(parser.stats().errors, 3);
+        assert_eq!(parser.stats().chars(), 1);
+
+        // Each replacement character is an error
+        let mut parser = Utf8Parser::new_lenient();
+        let _ = parser.feed(&[0xe0, 0x80, b'a', 0xf0]).count();
+        parser.finish().unwrap();
+        assert_eq!(parser.stats().errors, 3);
+        assert_eq!(parser.stats().chars(), 1);
+    }
+}